@@ -0,0 +1,118 @@
+use jui::{
+    App, Button, Element, EventStatus, Gradient, Hbox, List, PointerEvent, QuadStyle, RowHeight,
+    Scroll, Shadow, Size, Spacing, TextAlign, TextInput, TextStyle, Thing, Vbox, Weight,
+    WindowSettings, JETBRAINS_MONO,
+};
+
+pub fn main() {
+    let search = Element::new(Thing::TextInput(
+        TextInput::new("Search...").with_on_change(|text| println!("search: {text}")),
+    ))
+    .with_height(Size::Fit)
+    .with_padding(Spacing::all(6.0));
+
+    let sidebar_panel = Element::new(Thing::Quad {
+        style: QuadStyle::new([0.2, 0.2, 0.25, 1.0])
+            .with_radius(8.0)
+            .with_border(1.0, [0.35, 0.35, 0.45, 1.0])
+            .with_shadow(Shadow::new([0.0, 2.0], 8.0, [0.0, 0.0, 0.0, 0.5])),
+    })
+    .with_on_pointer(|thing, event| match (thing, event) {
+        (Thing::Quad { style }, PointerEvent::Pressed { .. }) => {
+            style.color = [0.3, 0.3, 0.4, 1.0];
+            EventStatus::Handled
+        }
+        (Thing::Quad { style }, PointerEvent::Released { .. }) => {
+            style.color = [0.2, 0.2, 0.25, 1.0];
+            EventStatus::Handled
+        }
+        _ => EventStatus::Ignored,
+    });
+
+    let rows = Element::new(Thing::List(List::new(
+        100_000,
+        RowHeight::Fixed(22.0),
+        |i| {
+            Element::new(Thing::Text {
+                text: format!("Row {i}"),
+                style: TextStyle::default().with_size(14.0),
+                text_color: [0.8, 0.8, 0.85, 1.0],
+                background_color: [0.0, 0.0, 0.0, 0.0],
+                align: (TextAlign::Start, TextAlign::Center),
+            })
+            .with_padding(Spacing::symmetric(6.0, 0.0))
+        },
+    )))
+    .with_height(Size::Percent(50.0));
+
+    let sidebar = Element::new(Thing::Vbox(
+        Vbox::new(vec![search, rows, sidebar_panel]).with_gap(4.0),
+    ))
+    .with_width(Size::Fixed(200.0));
+
+    let title = Element::new(Thing::Text {
+        text: "jui demo".to_string(),
+        style: TextStyle::default()
+            .with_size(20.0)
+            .with_weight(Weight::BOLD),
+        text_color: [1.0, 1.0, 1.0, 1.0],
+        background_color: [0.0, 0.0, 0.0, 0.0],
+        align: (TextAlign::Center, TextAlign::Center),
+    })
+    .with_padding(Spacing::symmetric(12.0, 8.0));
+
+    let mut clicks = 0;
+    let button = Element::new(Thing::Button(Button::new("Click me", move || {
+        clicks += 1;
+        println!("clicked {clicks} times");
+    })))
+    .with_width(Size::Fit)
+    .with_padding(Spacing::symmetric(12.0, 8.0));
+
+    let toolbar = Element::new(Thing::Hbox(Hbox::new(vec![title, button])))
+        .with_height(Size::Fit)
+        .with_background(
+            QuadStyle::new([0.1, 0.1, 0.4, 1.0]).with_gradient(Gradient::linear(
+                90.0,
+                &[(0.0, [0.1, 0.1, 0.4, 1.0]), (1.0, [0.3, 0.1, 0.4, 1.0])],
+            )),
+        );
+
+    let content = Element::new(Thing::TextMultiLine {
+        text: vec![
+            "ASDF KJASDF KJFJDFJ DFJ AJFADFDSF JSJDF JASDF KJASDF KJFJDFJ DFJ AJDSF JSJDF J"
+                .to_string(),
+            "KJFJDFJ DFJ AJDSF JSJDF JASDF KVDVDVSJASDFKJFJDFJ DFJ AJDSF JSJDF JASDF KJASDF"
+                .to_string(),
+            "ASDF KJASDF JSJDF JKJFJDFJ DFJ AJDSFASDF KJASDFVADSVZXC JSJDF JKJFJDFJ DFJ AJDSF"
+                .to_string(),
+            "AJDSF JSJDFXC JASDF KJASDF KJFJDFVCZX DFJAJDSF JSJDF JASDF KJASDF KJFJDFJ DFJ"
+                .to_string(),
+            "ASDF AJDSF JSJDF JKJASDF KJFJDFXZCVZCXVJ DFJASDF AJDSF JSJDF JKJASDF KJFJDFJ DFJ"
+                .to_string(),
+        ]
+        .into_iter()
+        .cycle()
+        .take(40)
+        .collect(),
+        style: TextStyle::new(JETBRAINS_MONO, 16.0).with_line_height(1.2),
+        wrap_text: false,
+        text_color: [1.0, 0.0, 0.0, 1.0],
+        background_color: [0.0, 0.0, 0.0, 1.0],
+    })
+    .with_padding(Spacing::all(8.0));
+    let content = Element::new(Thing::Scroll(Scroll::new(content).with_horizontal(true)));
+
+    let main_panel =
+        Element::new(Thing::Vbox(Vbox::new(vec![toolbar, content]).with_gap(4.0))).with_clip(true);
+
+    let root = Element::new(Thing::Hbox(
+        Hbox::new(vec![sidebar, main_panel]).with_gap(4.0),
+    ))
+    .with_padding(Spacing::all(4.0));
+
+    jui::run(App::new(root).with_window(WindowSettings {
+        title: "jui demo".to_string(),
+        ..Default::default()
+    }));
+}
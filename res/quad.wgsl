@@ -7,18 +7,30 @@ struct Quad {
     @location(2) size: vec2f,
     @location(3) color: vec4f,
     @location(4) border_color: vec4f,
-    @location(5) border: f32,
-    @location(6) radius: f32,
+    @location(5) border: vec4f, // top, right, bottom, left
+    @location(6) radius: vec4f, // top left, top right, bottom right, bottom left
+    @location(7) gradient: vec4f, // kind, then angle, radius and center, or blur
+    @location(8) stop_offsets: vec4f,
+    @location(9) stop_color_0: vec4f,
+    @location(10) stop_color_1: vec4f,
+    @location(11) stop_color_2: vec4f,
+    @location(12) stop_color_3: vec4f,
 }
 
 struct FragmentInput {
     @builtin(position) position: vec4f,
-    @location(0) origin: vec2f,
+    @location(0) origin: vec2f, // pixels
     @location(1) size: vec2f,
     @location(2) color: vec4f,
-    @location(3) radius: f32,
-    @location(4) border: f32,
+    @location(3) radius: vec4f,
+    @location(4) border: vec4f,
     @location(5) border_color: vec4f,
+    @location(6) gradient: vec4f,
+    @location(7) stop_offsets: vec4f,
+    @location(8) stop_color_0: vec4f,
+    @location(9) stop_color_1: vec4f,
+    @location(10) stop_color_2: vec4f,
+    @location(11) stop_color_3: vec4f,
 }
 
 struct Uniforms {
@@ -46,31 +58,151 @@ fn vs_main(vertex: Vertex, quad: Quad) -> FragmentInput {
     out.border_color = quad.border_color;
     out.radius = quad.radius;
     out.border = quad.border;
-    out.origin = ndc_origin;
+    out.origin = quad.origin;
     out.color = quad.color;
     out.size = quad.size;
+    out.gradient = quad.gradient;
+    out.stop_offsets = quad.stop_offsets;
+    out.stop_color_0 = quad.stop_color_0;
+    out.stop_color_1 = quad.stop_color_1;
+    out.stop_color_2 = quad.stop_color_2;
+    out.stop_color_3 = quad.stop_color_3;
     return out;
 }
 
-fn rounded_rect_sdf(frag_pos: vec2f, rect_center: vec2f, size: vec2f, radius: f32) -> f32 {
-    let q = vec2f(frag_pos.x - rect_center.x, frag_pos.y + rect_center.y);
+// Signed distance in pixels from frag_pos to the edge of the rectangle,
+// negative inside. size is half of the rectangle's width and height, and each
+// corner gets its own radius. Positions are in pixels with y pointing down.
+fn rounded_rect_sdf(frag_pos: vec2f, rect_center: vec2f, size: vec2f, radii: vec4f) -> f32 {
+    let q = frag_pos - rect_center;
+
+    // Only the radius of the corner nearest to frag_pos matters.
+    var radius: f32;
+    if q.x < 0.0 {
+        radius = select(radii.w, radii.x, q.y < 0.0);
+    } else {
+        radius = select(radii.z, radii.y, q.y < 0.0);
+    }
+
     let d = abs(q) - size + radius;
     return min(max(d.x, d.y), 0.0) - radius + length(max(d, vec2f(0.0, 0.0)));
 }
 
+// Approximation of the error function, good to about 5e-4. Abramowitz and
+// Stegun 7.1.27.
+fn erf(x: f32) -> f32 {
+    let a = abs(x);
+    let t = 1.0 + (0.278393 + (0.230389 + 0.078108 * a * a) * a) * a;
+    let t2 = t * t;
+    return sign(x) * (1.0 - 1.0 / (t2 * t2));
+}
+
+// A rounded rect convolved with a gaussian, approximated by blurring the
+// distance to its edge. blur is the distance over which the edge fades out,
+// which is about two standard deviations like in CSS.
+fn shadow(in: FragmentInput, center: vec2f, half_size: vec2f) -> vec4f {
+    let blur = in.gradient.y;
+    let shape_size = max(half_size - blur, vec2f(0.0));
+    let radius = min(in.radius, vec4f(min(shape_size.x, shape_size.y)));
+    let dist = rounded_rect_sdf(in.position.xy, center, shape_size, radius);
+
+    var coverage: f32;
+    if blur > 0.0 {
+        let sigma = blur / 2.0;
+        coverage = 0.5 - 0.5 * erf(dist / (sigma * sqrt(2.0)));
+    } else {
+        coverage = clamp(0.5 - dist, 0.0, 1.0);
+    }
+    return vec4f(in.color.rgb, in.color.a * coverage);
+}
+
+fn premultiply(color: vec4f) -> vec4f {
+    return vec4f(color.rgb * color.a, color.a);
+}
+
+// Premultiplied fill color of the quad at frag_pos, either the solid color or
+// the gradient. Unused stops repeat the last one, so walking all four is fine.
+fn fill_color(in: FragmentInput, frag_pos: vec2f, center: vec2f, half_size: vec2f) -> vec4f {
+    let kind = in.gradient.x;
+    if kind == 0.0 {
+        return premultiply(in.color);
+    }
+
+    var t: f32;
+    if kind == 1.0 {
+        // Same gradient line as CSS, just long enough to reach the corners.
+        let angle = in.gradient.y;
+        let dir = vec2f(sin(angle), -cos(angle));
+        let length = abs(2.0 * half_size.x * dir.x) + abs(2.0 * half_size.y * dir.y);
+        t = dot(frag_pos - center, dir) / max(length, 0.0001) + 0.5;
+    } else {
+        let gradient_center = center - half_size + in.gradient.zw * 2.0 * half_size;
+        var radius = in.gradient.y;
+        if radius <= 0.0 {
+            let farthest = abs(gradient_center - center) + half_size;
+            radius = length(farthest);
+        }
+        t = length(frag_pos - gradient_center) / max(radius, 0.0001);
+    }
+
+    let offsets = in.stop_offsets;
+    var color = premultiply(in.stop_color_0);
+    color = mix(color, premultiply(in.stop_color_1), clamp((t - offsets.x) / max(offsets.y - offsets.x, 0.0001), 0.0, 1.0));
+    color = mix(color, premultiply(in.stop_color_2), clamp((t - offsets.y) / max(offsets.z - offsets.y, 0.0001), 0.0, 1.0));
+    color = mix(color, premultiply(in.stop_color_3), clamp((t - offsets.z) / max(offsets.w - offsets.z, 0.0001), 0.0, 1.0));
+    return color;
+}
+
 @fragment
 fn fs_main(in: FragmentInput) -> @location(0) vec4f {
-    let pos = to_ndc(in.position.xy);
-    let dist = rounded_rect_sdf(pos, in.origin, in.size, in.radius);
-
-    var color: vec3f;
-    if dist < in.border {
-        color = in.color.xyz;
-    } else if dist < 0.0 {
-        color = in.border_color.xyz;
-    } else {
-        color = vec3f(0.0, 0.0, 0.0);
+    // The fragment position is in pixels measured from the top left, so flip
+    // the origin to match.
+    let center = vec2f(in.origin.x, uniforms.window_size.y - in.origin.y);
+    let half_size = in.size * uniforms.window_size.xy / 2.0;
+    if in.gradient.x == 3.0 {
+        let color = shadow(in, center, half_size);
+        if color.a <= 0.0 {
+            discard;
+        }
+        return color;
     }
 
-    return vec4f(color, 1.0);
+    let radius = min(in.radius, vec4f(min(half_size.x, half_size.y)));
+    let dist = rounded_rect_sdf(in.position.xy, center, half_size, radius);
+
+    // How much of this pixel is covered by the shape. The edge is smoothed
+    // over one pixel, centered on the exact boundary.
+    let coverage = clamp(0.5 - dist, 0.0, 1.0);
+    if coverage <= 0.0 {
+        discard;
+    }
+
+    // Same again for the inside edge of the border, blending the border into
+    // the fill. The inside edge is the rectangle shrunk by each side's border,
+    // with its corners rounded a little less to follow the outside edge.
+    // Colors are premultiplied while blending so a transparent fill doesn't
+    // darken the border.
+    var fill_coverage = 1.0;
+    if any(in.border > vec4f(0.0)) {
+        let top = in.border.x;
+        let right = in.border.y;
+        let bottom = in.border.z;
+        let left = in.border.w;
+        let inner_center = center + vec2f(left - right, top - bottom) / 2.0;
+        let inner_size = max(half_size - vec2f(left + right, top + bottom) / 2.0, vec2f(0.0));
+        let inner_radius = max(
+            radius - vec4f(max(top, left), max(top, right), max(bottom, right), max(bottom, left)),
+            vec4f(0.0),
+        );
+        let inner_dist = rounded_rect_sdf(in.position.xy, inner_center, inner_size, inner_radius);
+        fill_coverage = clamp(0.5 - inner_dist, 0.0, 1.0);
+    }
+    let fill = fill_color(in, in.position.xy, center, half_size);
+    let border = premultiply(in.border_color);
+    let blended = mix(border, fill, fill_coverage) * coverage;
+
+    if blended.a <= 0.0 {
+        discard;
+    }
+    return vec4f(blended.rgb / blended.a, blended.a);
 }
\ No newline at end of file
@@ -41,5 +41,7 @@ fn vs_main(vertex: Vertex) -> FragmentInput {
 
 @fragment
 fn fs_main(in: FragmentInput) -> @location(0) vec4f {
-    return vec4f(in.color.rgb, textureSample(t_diffuse, s_diffuse, in.tex_coords).a);
+    // Texture coordinates are in pixels, since the atlas can grow.
+    let size = vec2f(textureDimensions(t_diffuse));
+    return vec4f(in.color.rgb, textureSample(t_diffuse, s_diffuse, in.tex_coords / size).a);
 }
\ No newline at end of file
@@ -0,0 +1,58 @@
+use crate::{
+    font::FontRegistry,
+    layout::{Element, SceneRoot},
+};
+
+/// Settings for the window jui creates when an app is run.
+#[derive(Debug, Clone)]
+pub struct WindowSettings {
+    pub title: String,
+    pub width: u32,
+    pub height: u32,
+    pub resizable: bool,
+}
+
+impl Default for WindowSettings {
+    fn default() -> Self {
+        Self {
+            title: "jui".to_string(),
+            width: 800,
+            height: 600,
+            resizable: true,
+        }
+    }
+}
+
+/// A user supplied scene tree along with the window it should be drawn in.
+#[derive(Debug)]
+pub struct App {
+    pub root: Element,
+    pub window: WindowSettings,
+    pub fonts: FontRegistry,
+}
+
+impl App {
+    pub fn new(root: impl Into<Element>) -> Self {
+        Self {
+            root: root.into(),
+            window: WindowSettings::default(),
+            fonts: FontRegistry::new(),
+        }
+    }
+
+    pub fn with_window(mut self, window: WindowSettings) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Use `fonts` instead of just the bundled ones.
+    pub fn with_fonts(mut self, fonts: FontRegistry) -> Self {
+        self.fonts = fonts;
+        self
+    }
+}
+
+/// Open a window and run the app until the window is closed.
+pub fn run(app: App) {
+    pollster::block_on(SceneRoot::run(app));
+}
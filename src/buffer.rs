@@ -0,0 +1,45 @@
+use wgpu::{Buffer, BufferAddress, BufferDescriptor, BufferSlice, BufferUsages, Device, Queue};
+
+/// A GPU buffer that is replaced with a bigger one whenever the data written
+/// to it doesn't fit. It never shrinks.
+pub struct GrowableBuffer {
+    buffer: Buffer,
+    label: &'static str,
+    usage: BufferUsages,
+}
+
+impl GrowableBuffer {
+    /// `size` is the starting size in bytes. It's only a starting point, the
+    /// buffer grows as needed.
+    pub fn new(device: &Device, label: &'static str, usage: BufferUsages, size: u64) -> Self {
+        Self {
+            buffer: Self::create(device, label, usage, size),
+            label,
+            usage,
+        }
+    }
+
+    fn create(device: &Device, label: &'static str, usage: BufferUsages, size: u64) -> Buffer {
+        device.create_buffer(&BufferDescriptor {
+            label: Some(label),
+            size,
+            usage: usage | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// Copy `data` to the start of the buffer, growing it to the next power of
+    /// two first if it's too small.
+    pub fn write(&mut self, device: &Device, queue: &Queue, data: &[u8]) {
+        let needed = data.len() as BufferAddress;
+        if needed > self.buffer.size() {
+            let size = needed.next_power_of_two();
+            self.buffer = Self::create(device, self.label, self.usage, size);
+        }
+        queue.write_buffer(&self.buffer, 0, data);
+    }
+
+    pub fn slice(&self) -> BufferSlice<'_> {
+        self.buffer.slice(..)
+    }
+}
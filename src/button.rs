@@ -0,0 +1,176 @@
+use crate::{
+    event::{EventStatus, KeyboardEvent, PointerEvent},
+    font::TextStyle,
+    layout::{LayoutNode, TextMeasure},
+    quad::QuadStyle,
+    renderer::State,
+};
+use std::fmt;
+use winit::{
+    event::MouseButton,
+    keyboard::{Key, NamedKey},
+};
+
+/// How a button is drawn in one of its states.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ButtonStyle {
+    pub background: QuadStyle,
+    pub text_color: [f32; 4],
+}
+
+/// The look of a button for each state it can be in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ButtonStyles {
+    pub normal: ButtonStyle,
+    pub hover: ButtonStyle,
+    pub pressed: ButtonStyle,
+    pub disabled: ButtonStyle,
+}
+
+impl Default for ButtonStyles {
+    fn default() -> Self {
+        Self {
+            normal: ButtonStyle {
+                background: QuadStyle::new([0.25, 0.25, 0.3, 1.0]).with_radius(4.0),
+                text_color: [1.0, 1.0, 1.0, 1.0],
+            },
+            hover: ButtonStyle {
+                background: QuadStyle::new([0.32, 0.32, 0.4, 1.0]).with_radius(4.0),
+                text_color: [1.0, 1.0, 1.0, 1.0],
+            },
+            pressed: ButtonStyle {
+                background: QuadStyle::new([0.18, 0.18, 0.22, 1.0]).with_radius(4.0),
+                text_color: [0.9, 0.9, 0.9, 1.0],
+            },
+            disabled: ButtonStyle {
+                background: QuadStyle::new([0.2, 0.2, 0.2, 1.0]).with_radius(4.0),
+                text_color: [0.5, 0.5, 0.5, 1.0],
+            },
+        }
+    }
+}
+
+struct ClickHandler(Box<dyn FnMut()>);
+
+impl fmt::Debug for ClickHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ClickHandler")
+    }
+}
+
+/// A clickable label. `on_click` runs when the left mouse button is pressed
+/// and then released over the button, or when Enter or Space is pressed while
+/// the button has focus.
+#[derive(Debug)]
+pub struct Button {
+    pub label: String,
+    pub text_style: TextStyle,
+    pub styles: ButtonStyles,
+    pub disabled: bool,
+    on_click: ClickHandler,
+    hovered: bool,
+    pressed: bool,
+}
+
+impl Button {
+    pub fn new(label: &str, on_click: impl FnMut() + 'static) -> Self {
+        Self {
+            label: label.to_string(),
+            text_style: TextStyle::default(),
+            styles: ButtonStyles::default(),
+            disabled: false,
+            on_click: ClickHandler(Box::new(on_click)),
+            hovered: false,
+            pressed: false,
+        }
+    }
+
+    pub fn with_text_style(mut self, text_style: TextStyle) -> Self {
+        self.text_style = text_style;
+        self
+    }
+
+    pub fn with_styles(mut self, styles: ButtonStyles) -> Self {
+        self.styles = styles;
+        self
+    }
+
+    pub fn with_disabled(mut self, disabled: bool) -> Self {
+        self.disabled = disabled;
+        self
+    }
+
+    /// The style matching the state the button is currently in.
+    pub fn style(&self) -> &ButtonStyle {
+        if self.disabled {
+            &self.styles.disabled
+        } else if self.pressed {
+            &self.styles.pressed
+        } else if self.hovered {
+            &self.styles.hover
+        } else {
+            &self.styles.normal
+        }
+    }
+
+    pub(crate) fn draw(&self, state: &mut State, node: &LayoutNode) {
+        let style = self.style();
+        state.add_quad(style.background.clone(), &node.bbox);
+
+        let center = node.content.center();
+        state.add_text_centered(
+            &self.label,
+            &self.text_style,
+            center.0,
+            center.1,
+            style.text_color,
+        );
+    }
+
+    pub(crate) fn fit_size(&self, measure: &mut dyn TextMeasure) -> (f32, f32) {
+        let (width, height, _) = measure.measure(&self.label, &self.text_style, None);
+        (width, height)
+    }
+
+    pub(crate) fn set_hovered(&mut self, hovered: bool) {
+        self.hovered = hovered;
+    }
+
+    pub(crate) fn handle_pointer(
+        &mut self,
+        node: &LayoutNode,
+        event: &PointerEvent,
+    ) -> EventStatus {
+        match event {
+            PointerEvent::Pressed {
+                button: MouseButton::Left,
+                ..
+            } => {
+                self.pressed = !self.disabled;
+                EventStatus::Handled
+            }
+            PointerEvent::Released {
+                pos,
+                button: MouseButton::Left,
+            } => {
+                let clicked = self.pressed && !self.disabled && node.bbox.inside(*pos);
+                self.pressed = false;
+                if clicked {
+                    (self.on_click.0)();
+                }
+                EventStatus::Handled
+            }
+            _ => EventStatus::Ignored,
+        }
+    }
+
+    pub(crate) fn handle_key(&mut self, event: &KeyboardEvent) -> EventStatus {
+        match event.key {
+            Key::Named(NamedKey::Enter | NamedKey::Space) if !self.disabled => {
+                (self.on_click.0)();
+                EventStatus::Handled
+            }
+            _ => EventStatus::Ignored,
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use crate::layout::{TextMeasure, Thing};
+use std::fmt;
+use winit::{
+    event::MouseButton,
+    keyboard::{Key, ModifiersState},
+};
+
+/// How many pixels one line of a mouse wheel scrolls.
+pub const SCROLL_LINE_PIXELS: f32 = 40.0;
+
+/// A mouse event. Positions are in the same space as `Bbox`, with (0, 0) in
+/// the bottom left corner of the window.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PointerEvent {
+    Moved {
+        pos: (f32, f32),
+    },
+    Pressed {
+        pos: (f32, f32),
+        button: MouseButton,
+    },
+    Released {
+        pos: (f32, f32),
+        button: MouseButton,
+    },
+    /// `delta` is in pixels. Positive values scroll up and to the left, the
+    /// same as winit.
+    Scrolled {
+        pos: (f32, f32),
+        delta: (f32, f32),
+    },
+}
+
+impl PointerEvent {
+    pub fn pos(&self) -> (f32, f32) {
+        match self {
+            PointerEvent::Moved { pos }
+            | PointerEvent::Pressed { pos, .. }
+            | PointerEvent::Released { pos, .. }
+            | PointerEvent::Scrolled { pos, .. } => *pos,
+        }
+    }
+}
+
+/// Whether an event was used up. Ignored events bubble up to the parent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventStatus {
+    Handled,
+    Ignored,
+}
+
+type PointerFn = dyn FnMut(&mut Thing, &PointerEvent) -> EventStatus;
+
+/// A callback run when a pointer event reaches an element. It is given the
+/// element's thing so it can change what gets drawn.
+pub struct PointerHandler(Box<PointerFn>);
+
+impl PointerHandler {
+    pub fn new(handler: impl FnMut(&mut Thing, &PointerEvent) -> EventStatus + 'static) -> Self {
+        Self(Box::new(handler))
+    }
+
+    pub fn call(&mut self, thing: &mut Thing, event: &PointerEvent) -> EventStatus {
+        (self.0)(thing, event)
+    }
+}
+
+impl fmt::Debug for PointerHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PointerHandler")
+    }
+}
+
+/// A key press sent to the focused element. Key releases are not sent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyboardEvent {
+    pub key: Key,
+    /// The text the key would type, if any.
+    pub text: Option<String>,
+    pub modifiers: ModifiersState,
+}
+
+/// Everything from the rest of jui that widgets may need while handling an
+/// event.
+pub struct EventContext<'a> {
+    pub measure: &'a mut dyn TextMeasure,
+}
@@ -0,0 +1,217 @@
+use crate::{
+    event::{EventStatus, KeyboardEvent},
+    layout::{Element, LayoutNode},
+    renderer::State,
+};
+use winit::keyboard::{Key, NamedKey};
+
+/// Keeps track of which element key presses are sent to. Focus moves through
+/// the focusable elements in layout order with Tab and Shift+Tab.
+///
+/// Elements are referred to by their path, the child indices leading to them
+/// from the root.
+#[derive(Debug)]
+pub struct FocusManager {
+    focused: Option<Vec<usize>>,
+    pub ring_color: [f32; 4],
+    pub ring_width: f32,
+    /// How far outside of the focused element the ring is drawn.
+    pub ring_offset: f32,
+}
+
+impl Default for FocusManager {
+    fn default() -> Self {
+        Self {
+            focused: None,
+            ring_color: [0.35, 0.6, 1.0, 1.0],
+            ring_width: 2.0,
+            ring_offset: 2.0,
+        }
+    }
+}
+
+impl FocusManager {
+    pub fn focused(&self) -> Option<&[usize]> {
+        self.focused.as_deref()
+    }
+
+    /// For when the tree changes under the focused element without it moving
+    /// focus, like a `List` building rows.
+    pub(crate) fn focused_mut(&mut self) -> &mut Option<Vec<usize>> {
+        &mut self.focused
+    }
+
+    /// Move focus to the element at `focus`, or nowhere, telling both the old
+    /// and the new element about it.
+    pub fn set_focus(&mut self, root: &mut Element, focus: Option<Vec<usize>>) {
+        if let Some(elem) = self.focused.as_ref().and_then(|path| root.find_mut(path)) {
+            elem.thing.set_focused(false);
+        }
+        if let Some(elem) = focus.as_ref().and_then(|path| root.find_mut(path)) {
+            elem.thing.set_focused(true);
+        }
+        self.focused = focus;
+    }
+
+    /// Move focus to the next focusable element, or the previous one when
+    /// going backwards, wrapping around at the ends.
+    pub fn focus_next(&mut self, root: &mut Element, backwards: bool) {
+        let mut focusable = vec![];
+        root.collect_focusable(&mut vec![], &mut focusable);
+        if focusable.is_empty() {
+            return;
+        }
+
+        let current = self
+            .focused
+            .as_ref()
+            .and_then(|focused| focusable.iter().position(|path| path == focused));
+        let count = focusable.len();
+        let next = match (current, backwards) {
+            (Some(i), false) => (i + 1) % count,
+            (Some(i), true) => (i + count - 1) % count,
+            (None, false) => 0,
+            (None, true) => count - 1,
+        };
+
+        let focus = focusable.swap_remove(next);
+        self.set_focus(root, Some(focus));
+    }
+
+    /// Tab moves the focus, every other key goes to the focused element.
+    pub fn handle_key(&mut self, root: &mut Element, event: &KeyboardEvent) -> EventStatus {
+        if event.key == Key::Named(NamedKey::Tab) {
+            self.focus_next(root, event.modifiers.shift_key());
+            return EventStatus::Handled;
+        }
+
+        match self.focused.as_ref().and_then(|path| root.find_mut(path)) {
+            Some(elem) => elem.handle_key(event),
+            None => EventStatus::Ignored,
+        }
+    }
+
+    /// Draw a ring around the focused element, cut off wherever the element
+    /// itself is, such as at the edges of a scroll.
+    pub fn draw(&self, state: &mut State, root: &Element, layout: &LayoutNode) {
+        let Some(path) = &self.focused else {
+            return;
+        };
+        let Some(node) = layout.find(path) else {
+            return;
+        };
+
+        let clip = root.clip_of(layout, path);
+        if let Some(clip) = &clip {
+            state.push_clip(clip);
+        }
+        let ring = node.bbox.expand(self.ring_offset + self.ring_width);
+        state.add_outline(self.ring_color, &ring, self.ring_width);
+        if clip.is_some() {
+            state.pop_clip();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        button::Button,
+        layout::{Hbox, Thing, Vbox},
+        text_input::TextInput,
+    };
+    use winit::keyboard::ModifiersState;
+
+    /// A button, a disabled button, then a text input and a button side by
+    /// side.
+    fn tree() -> Element {
+        let row = Hbox::new(vec![
+            Element::new(Thing::TextInput(TextInput::new(""))),
+            Element::new(Thing::Button(Button::new("b", || {}))),
+        ]);
+        Element::new(Thing::Vbox(Vbox::new(vec![
+            Element::new(Thing::Button(Button::new("a", || {}))),
+            Element::new(Thing::Button(Button::new("off", || {}).with_disabled(true))),
+            Element::new(Thing::Hbox(row)),
+        ])))
+    }
+
+    fn key(key: Key, text: Option<&str>, shift: bool) -> KeyboardEvent {
+        KeyboardEvent {
+            key,
+            text: text.map(str::to_string),
+            modifiers: if shift {
+                ModifiersState::SHIFT
+            } else {
+                ModifiersState::empty()
+            },
+        }
+    }
+
+    fn tab(shift: bool) -> KeyboardEvent {
+        key(Key::Named(NamedKey::Tab), Some("\t"), shift)
+    }
+
+    fn input_text(root: &mut Element) -> String {
+        match &root.find_mut(&[2, 0]).unwrap().thing {
+            Thing::TextInput(input) => input.text().to_string(),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn tab_skips_disabled_buttons_and_wraps_around() {
+        let mut root = tree();
+        let mut focus = FocusManager::default();
+
+        let mut order = vec![];
+        for _ in 0..4 {
+            focus.handle_key(&mut root, &tab(false));
+            order.push(focus.focused().unwrap().to_vec());
+        }
+        assert_eq!(order, vec![vec![0], vec![2, 0], vec![2, 1], vec![0]]);
+    }
+
+    #[test]
+    fn shift_tab_goes_backwards_and_wraps_around() {
+        let mut root = tree();
+        let mut focus = FocusManager::default();
+
+        focus.handle_key(&mut root, &tab(true));
+        assert_eq!(focus.focused(), Some(&[2, 1][..]));
+        focus.handle_key(&mut root, &tab(true));
+        assert_eq!(focus.focused(), Some(&[2, 0][..]));
+        focus.handle_key(&mut root, &tab(true));
+        assert_eq!(focus.focused(), Some(&[0][..]));
+        focus.handle_key(&mut root, &tab(true));
+        assert_eq!(focus.focused(), Some(&[2, 1][..]));
+    }
+
+    #[test]
+    fn tab_moves_focus_before_the_focused_element_sees_it() {
+        let mut root = tree();
+        let mut focus = FocusManager::default();
+        focus.set_focus(&mut root, Some(vec![2, 0]));
+
+        let typed = key(Key::Character("x".into()), Some("x"), false);
+        assert_eq!(focus.handle_key(&mut root, &typed), EventStatus::Handled);
+        assert_eq!(
+            focus.handle_key(&mut root, &tab(false)),
+            EventStatus::Handled
+        );
+
+        assert_eq!(focus.focused(), Some(&[2, 1][..]));
+        assert_eq!(input_text(&mut root), "x");
+    }
+
+    #[test]
+    fn keys_without_focus_are_ignored() {
+        let mut root = tree();
+        let mut focus = FocusManager::default();
+        let typed = key(Key::Character("x".into()), Some("x"), false);
+
+        assert_eq!(focus.handle_key(&mut root, &typed), EventStatus::Ignored);
+        assert_eq!(focus.focused(), None);
+    }
+}
@@ -0,0 +1,715 @@
+use crate::layout::TextMeasure;
+use freetype::Face;
+use rustybuzz::{ttf_parser::Tag, Direction, UnicodeBuffer};
+use std::{
+    borrow::Cow,
+    cell::Cell,
+    collections::HashMap,
+    error, fmt, fs, io, iter,
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use unicode_bidi::BidiInfo;
+use unicode_linebreak::{break_property, BreakClass};
+use unicode_script::{Script, UnicodeScript};
+
+/// The family of the bundled Roboto font, the default for text.
+pub const ROBOTO: &str = "Roboto";
+/// The family of the bundled JetBrains Mono font.
+pub const JETBRAINS_MONO: &str = "JetBrains Mono";
+
+/// How bold a font is, on the usual scale from 100 (thin) to 900 (black).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Weight(pub u16);
+
+impl Weight {
+    pub const THIN: Weight = Weight(100);
+    pub const LIGHT: Weight = Weight(300);
+    pub const NORMAL: Weight = Weight(400);
+    pub const MEDIUM: Weight = Weight(500);
+    pub const BOLD: Weight = Weight(700);
+    pub const BLACK: Weight = Weight(900);
+}
+
+impl Default for Weight {
+    fn default() -> Self {
+        Weight::NORMAL
+    }
+}
+
+/// Which font text is drawn with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextStyle {
+    /// Name of a family in the `FontRegistry`. Families that aren't there fall
+    /// back to Roboto.
+    pub family: Arc<str>,
+    /// Size in pixels.
+    pub size: f32,
+    /// The registered weight of the family closest to this one is used.
+    pub weight: Weight,
+    /// Multiplies the line height the font asks for.
+    pub line_height: f32,
+}
+
+impl TextStyle {
+    pub fn new(family: impl Into<Arc<str>>, size: f32) -> Self {
+        Self {
+            family: family.into(),
+            size,
+            weight: Weight::NORMAL,
+            line_height: 1.0,
+        }
+    }
+
+    pub fn with_family(mut self, family: impl Into<Arc<str>>) -> Self {
+        self.family = family.into();
+        self
+    }
+
+    pub fn with_size(mut self, size: f32) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn with_weight(mut self, weight: Weight) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    pub fn with_line_height(mut self, line_height: f32) -> Self {
+        self.line_height = line_height;
+        self
+    }
+}
+
+/// Vertical metrics of a font at a size, in pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FontMetrics {
+    /// How far the font reaches above the baseline.
+    pub ascender: f32,
+    /// How far the font reaches below the baseline, as a negative number.
+    pub descender: f32,
+    /// Extra space the font wants between the descender of one line and the
+    /// ascender of the next. FreeType rounds each metric to whole pixels on
+    /// its own, so this can be a pixel below zero to keep the line height the
+    /// one it has.
+    pub line_gap: f32,
+}
+
+impl FontMetrics {
+    /// The distance between the baselines of two lines.
+    pub fn line_height(&self) -> f32 {
+        self.ascender - self.descender + self.line_gap
+    }
+
+    /// How far below the top of a line `line_height` tall its baseline is.
+    /// Space the line has beyond the ascender and descender is split evenly
+    /// above and below, like CSS does.
+    pub fn baseline(&self, line_height: f32) -> f32 {
+        let leading = line_height - (self.ascender - self.descender);
+        leading / 2.0 + self.ascender
+    }
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self::new(ROBOTO, 18.0)
+    }
+}
+
+/// Why a font couldn't be loaded.
+#[derive(Debug)]
+pub enum FontError {
+    /// The file couldn't be read.
+    Io(io::Error),
+    /// The data isn't a font FreeType and the shaper both understand.
+    Font(freetype::Error),
+}
+
+impl fmt::Display for FontError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FontError::Io(err) => write!(f, "couldn't read font: {err}"),
+            FontError::Font(err) => write!(f, "couldn't load font: {err}"),
+        }
+    }
+}
+
+impl error::Error for FontError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            FontError::Io(err) => Some(err),
+            FontError::Font(err) => Some(err),
+        }
+    }
+}
+
+impl From<io::Error> for FontError {
+    fn from(err: io::Error) -> Self {
+        FontError::Io(err)
+    }
+}
+
+impl From<freetype::Error> for FontError {
+    fn from(err: freetype::Error) -> Self {
+        FontError::Font(err)
+    }
+}
+
+/// A font loaded into a `FontRegistry`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FontId(usize);
+
+/// A glyph placed by shaping. Everything is in pixels, with y going up.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct ShapedGlyph {
+    /// The font the glyph is from, which is a fallback if the font the text
+    /// asked for doesn't have the char.
+    pub font: FontId,
+    /// Index of the glyph in its font, not a char.
+    pub id: u32,
+    /// How far to move the pen after drawing the glyph.
+    pub advance: (f32, f32),
+    /// Where the glyph is drawn relative to the pen.
+    pub offset: (f32, f32),
+    /// Byte offset of the first char the glyph was made from. Every glyph
+    /// made from the same chars has the same cluster.
+    pub cluster: usize,
+    /// Whether the glyph is part of right to left text.
+    pub rtl: bool,
+}
+
+struct Font {
+    family: String,
+    weight: Weight,
+    /// Renders the glyphs.
+    face: Face<&'static [u8]>,
+    /// Picks and places the glyphs, from the same data as `face`.
+    shaper: rustybuzz::Face<'static>,
+    /// The size the face was last set to, in 26.6 fixed point like FreeType,
+    /// and whether that worked. Faces with only a few fixed sizes, like bitmap
+    /// fonts, can't be set to most sizes.
+    size: Cell<(u32, bool)>,
+}
+
+/// The fonts text can be drawn with. Starts out with the bundled Roboto and
+/// JetBrains Mono, more can be loaded from files or bytes before the app is
+/// run.
+///
+/// Chars that a font doesn't have are drawn with the first of the fallback
+/// fonts that does, so fonts covering other scripts or symbols can be loaded
+/// and added there.
+pub struct FontRegistry {
+    library: freetype::Library,
+    fonts: Vec<Font>,
+    fallbacks: Vec<FontId>,
+    /// The contents of every file loaded so far, so loading one again doesn't
+    /// keep another copy.
+    files: HashMap<PathBuf, &'static [u8]>,
+}
+
+impl fmt::Debug for FontRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.fonts.iter().map(|font| (&font.family, font.weight)))
+            .finish()
+    }
+}
+
+impl FontRegistry {
+    pub fn new() -> Self {
+        let mut registry = Self {
+            library: freetype::Library::init().unwrap(),
+            fonts: vec![],
+            fallbacks: vec![],
+            files: HashMap::new(),
+        };
+        // Roboto has to come first, it's what unknown families fall back to.
+        registry
+            .load_bytes(
+                ROBOTO,
+                Weight::NORMAL,
+                include_bytes!("../res/Roboto-Regular.ttf"),
+            )
+            .unwrap();
+        registry
+            .load_bytes(
+                JETBRAINS_MONO,
+                Weight::NORMAL,
+                include_bytes!("../res/JetBrainsMono-Regular.ttf"),
+            )
+            .unwrap();
+        registry.fallbacks = vec![FontId(0), FontId(1)];
+        registry
+    }
+
+    /// Load a font file as the given family and weight. Each file is read
+    /// once and kept in memory for the rest of the program, however many
+    /// times it's loaded.
+    pub fn load_file(
+        &mut self,
+        family: &str,
+        weight: Weight,
+        path: impl AsRef<Path>,
+    ) -> Result<FontId, FontError> {
+        let path = fs::canonicalize(path)?;
+        let bytes = match self.files.get(&path) {
+            Some(bytes) => *bytes,
+            None => {
+                let bytes: &'static [u8] = Vec::leak(fs::read(&path)?);
+                self.files.insert(path, bytes);
+                bytes
+            }
+        };
+        self.load_bytes(family, weight, bytes)
+    }
+
+    /// Load a font from the contents of a font file, as the given family and
+    /// weight. Static bytes, like from `include_bytes!`, are used as they are.
+    /// Owned bytes are kept in memory for the rest of the program.
+    pub fn load_bytes(
+        &mut self,
+        family: &str,
+        weight: Weight,
+        bytes: impl Into<Cow<'static, [u8]>>,
+    ) -> Result<FontId, FontError> {
+        let bytes: &'static [u8] = match bytes.into() {
+            Cow::Borrowed(bytes) => bytes,
+            Cow::Owned(bytes) => Vec::leak(bytes),
+        };
+        let face = self.library.new_memory_face2(bytes, 0)?;
+        let shaper =
+            rustybuzz::Face::from_slice(bytes, 0).ok_or(freetype::Error::UnknownFileFormat)?;
+        self.fonts.push(Font {
+            family: family.to_string(),
+            weight,
+            face,
+            shaper,
+            size: Cell::new((0, false)),
+        });
+        Ok(FontId(self.fonts.len() - 1))
+    }
+
+    /// The font in `family` with the weight closest to `weight`, or the
+    /// default font if there's no such family.
+    pub fn find(&self, family: &str, weight: Weight) -> FontId {
+        self.fonts
+            .iter()
+            .enumerate()
+            .filter(|(_, font)| font.family == family)
+            .min_by_key(|(_, font)| font.weight.0.abs_diff(weight.0))
+            .map_or(FontId(0), |(i, _)| FontId(i))
+    }
+
+    /// The fonts searched, in order, for chars missing from the font text is
+    /// drawn with. Starts out as the bundled fonts.
+    pub fn fallbacks(&self) -> &[FontId] {
+        &self.fallbacks
+    }
+
+    pub fn set_fallbacks(&mut self, fallbacks: Vec<FontId>) {
+        self.fallbacks = fallbacks;
+    }
+
+    /// The face of `font`, set to `size` in 26.6 fixed point pixels, or
+    /// `None` if it can't be drawn at that size.
+    pub(crate) fn face(&self, font: FontId, size: u32) -> Option<&Face<&'static [u8]>> {
+        let font = &self.fonts[font.0];
+        if font.size.get().0 != size {
+            let sized = font.face.set_char_size(size as isize, 0, 0, 0).is_ok();
+            font.size.set((size, sized));
+        }
+        font.size.get().1.then_some(&font.face)
+    }
+
+    /// Vertical metrics of `font` at `size` pixels, as FreeType has them for
+    /// the face at that size.
+    pub fn metrics(&self, font: FontId, size: f32) -> FontMetrics {
+        let face = self.face(font, fixed_size(size));
+        match face.and_then(|face| face.size_metrics()) {
+            Some(metrics) => FontMetrics {
+                ascender: metrics.ascender as f32 / 64.0,
+                descender: metrics.descender as f32 / 64.0,
+                line_gap: (metrics.height - metrics.ascender + metrics.descender) as f32 / 64.0,
+            },
+            None => FontMetrics {
+                ascender: size,
+                descender: 0.0,
+                line_gap: 0.0,
+            },
+        }
+    }
+
+    /// Turn `text` into glyphs of `font` at `size` pixels, laid out on a
+    /// single line. This is where kerning, ligatures and the rules of complex
+    /// scripts are applied, so there isn't always one glyph per char.
+    ///
+    /// The glyphs come out in the order they're drawn, left to right, with
+    /// right to left text such as Arabic and Hebrew reordered by the Unicode
+    /// bidi algorithm. Each direction is split into runs of chars with the
+    /// same script and font, falling back for chars `font` doesn't have, and
+    /// each run is shaped on its own.
+    pub(crate) fn shape(&self, font: FontId, size: f32, text: &str) -> Vec<ShapedGlyph> {
+        let mut glyphs = vec![];
+        let bidi = BidiInfo::new(text, None);
+        for paragraph in &bidi.paragraphs {
+            let (levels, level_runs) = bidi.visual_runs(paragraph, paragraph.range.clone());
+            for level_run in level_runs {
+                let rtl = levels[level_run.start].is_rtl();
+                let mut runs = self.split_runs(font, text, level_run);
+                // Runs are found in reading order, which is drawn backwards
+                // from right to left.
+                if rtl {
+                    runs.reverse();
+                }
+                for (range, run_font, script) in runs {
+                    self.shape_run(run_font, size, text, range, script, rtl, &mut glyphs);
+                }
+            }
+        }
+        glyphs
+    }
+
+    /// Split the chars of `text` in `range`, all going in the same direction,
+    /// into runs with the same font and script.
+    fn split_runs(
+        &self,
+        font: FontId,
+        text: &str,
+        range: Range<usize>,
+    ) -> Vec<(Range<usize>, FontId, Script)> {
+        let mut runs: Vec<(Range<usize>, FontId, Script)> = vec![];
+        for (i, c) in text[range.clone()].char_indices() {
+            let i = range.start + i;
+            let previous = runs.last().map(|(_, font, _)| *font);
+            let char_font = self.font_for(font, previous, c);
+            let char_script = c.script();
+            match runs.last_mut() {
+                Some((run, run_font, run_script))
+                    if *run_font == char_font
+                        && (!is_real_script(char_script) || *run_script == char_script) =>
+                {
+                    run.end = i + c.len_utf8();
+                }
+                // Punctuation and spaces at the start don't decide the script.
+                Some((run, run_font, run_script))
+                    if *run_font == char_font && !is_real_script(*run_script) =>
+                {
+                    run.end = i + c.len_utf8();
+                    *run_script = char_script;
+                }
+                _ => runs.push((i..i + c.len_utf8(), char_font, char_script)),
+            }
+        }
+        runs
+    }
+
+    /// Shape the chars of `text` in `range` and add them to `glyphs`.
+    #[allow(clippy::too_many_arguments)]
+    fn shape_run(
+        &self,
+        font: FontId,
+        size: f32,
+        text: &str,
+        range: Range<usize>,
+        script: Script,
+        rtl: bool,
+        glyphs: &mut Vec<ShapedGlyph>,
+    ) {
+        let shaper = &self.fonts[font.0].shaper;
+        let mut buffer = UnicodeBuffer::new();
+        buffer.push_str(&text[range.clone()]);
+        buffer.guess_segment_properties();
+        if let Some(script) = script
+            .short_name()
+            .as_bytes()
+            .try_into()
+            .ok()
+            .and_then(|tag| rustybuzz::Script::from_iso15924_tag(Tag::from_bytes(tag)))
+        {
+            buffer.set_script(script);
+        }
+        buffer.set_direction(if rtl {
+            Direction::RightToLeft
+        } else {
+            Direction::LeftToRight
+        });
+        let shaped = rustybuzz::shape(shaper, &[], buffer);
+
+        let scale = size / shaper.units_per_em() as f32;
+        glyphs.extend(
+            shaped
+                .glyph_infos()
+                .iter()
+                .zip(shaped.glyph_positions())
+                .map(|(info, pos)| ShapedGlyph {
+                    font,
+                    id: info.glyph_id,
+                    advance: (pos.x_advance as f32 * scale, pos.y_advance as f32 * scale),
+                    offset: (pos.x_offset as f32 * scale, pos.y_offset as f32 * scale),
+                    cluster: range.start + info.cluster as usize,
+                    rtl,
+                }),
+        );
+    }
+
+    /// The font `c` is drawn with when the text asks for `font`: that one if
+    /// it has the char, otherwise the first fallback that does. Whitespace and
+    /// combining marks stay in the font of the char before them where
+    /// possible, so they don't split a run or get separated from what they
+    /// combine with. Chars no font has are left to `font` to draw as missing.
+    fn font_for(&self, font: FontId, previous: Option<FontId>, c: char) -> FontId {
+        let has_glyph = |font: FontId| self.fonts[font.0].shaper.glyph_index(c).is_some();
+
+        if let Some(previous) = previous {
+            let joins = c.is_whitespace()
+                || matches!(
+                    break_property(c as u32),
+                    BreakClass::CombiningMark | BreakClass::ZeroWidthJoiner
+                );
+            if joins && has_glyph(previous) {
+                return previous;
+            }
+        }
+
+        iter::once(font)
+            .chain(self.fallbacks.iter().copied())
+            .find(|&font| has_glyph(font))
+            .unwrap_or(font)
+    }
+}
+
+/// Text can be measured with the fonts alone, without a GPU, so a layout can
+/// be computed anywhere.
+impl TextMeasure for FontRegistry {
+    fn string_width(&mut self, s: &str, style: &TextStyle) -> f32 {
+        let font = self.find(&style.family, style.weight);
+        self.shape(font, style.size, s)
+            .iter()
+            .map(|glyph| glyph.advance.0)
+            .sum()
+    }
+
+    fn line_height(&self, style: &TextStyle) -> f32 {
+        let font = self.find(&style.family, style.weight);
+        self.metrics(font, style.size).line_height() * style.line_height
+    }
+
+    /// The text is shaped once. A glyph made from several chars, like a
+    /// ligature, has its width split evenly between them. Right to left text
+    /// starts at the right of its glyphs.
+    fn caret_positions(&mut self, text: &str, style: &TextStyle) -> Vec<(usize, f32)> {
+        let font = self.find(&style.family, style.weight);
+        // The cluster, its left and right edge, and which way it goes.
+        let mut clusters: Vec<(usize, f32, f32, bool)> = vec![];
+        let mut pen = 0.0;
+        for glyph in self.shape(font, style.size, text) {
+            let next = pen + glyph.advance.0;
+            match clusters.last_mut() {
+                Some((cluster, _, right, _)) if *cluster == glyph.cluster => *right = next,
+                _ => clusters.push((glyph.cluster, pen, next, glyph.rtl)),
+            }
+            pen = next;
+        }
+        clusters.sort_by_key(|(cluster, ..)| *cluster);
+
+        let mut positions = Vec::with_capacity(text.len() + 1);
+        for (i, &(start, left, right, rtl)) in clusters.iter().enumerate() {
+            let end = clusters.get(i + 1).map_or(text.len(), |(next, ..)| *next);
+            let chars = text[start..end].char_indices().map(|(j, _)| start + j);
+            let count = text[start..end].chars().count().max(1) as f32;
+            for (k, j) in chars.enumerate() {
+                let along = (right - left) * k as f32 / count;
+                positions.push((j, if rtl { right - along } else { left + along }));
+            }
+        }
+        let end = match clusters.last() {
+            Some(&(_, left, _, true)) => left,
+            Some(&(_, _, right, false)) => right,
+            None => 0.0,
+        };
+        positions.push((text.len(), end));
+        positions
+    }
+}
+
+impl Default for FontRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether chars of `script` belong to a writing system, rather than being
+/// shared between them like digits, punctuation and combining marks.
+fn is_real_script(script: Script) -> bool {
+    !matches!(script, Script::Common | Script::Inherited | Script::Unknown)
+}
+
+/// A font size in pixels as the 26.6 fixed point number FreeType uses.
+pub(crate) fn fixed_size(size: f32) -> u32 {
+    (size * 64.0).round().max(64.0) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glyph_ids(fonts: &FontRegistry, text: &str) -> Vec<u32> {
+        fonts
+            .shape(FontId(0), 16.0, text)
+            .iter()
+            .map(|glyph| glyph.id)
+            .collect()
+    }
+
+    #[test]
+    fn runs_split_by_script_and_font() {
+        let fonts = FontRegistry::new();
+        let text = "(abc) абв → x";
+        let runs: Vec<_> = fonts
+            .split_runs(FontId(0), text, 0..text.len())
+            .into_iter()
+            .map(|(range, font, script)| (&text[range], font, script))
+            .collect();
+
+        assert_eq!(
+            runs,
+            vec![
+                ("(abc) ", FontId(0), Script::Latin),
+                ("абв ", FontId(0), Script::Cyrillic),
+                // Roboto has no arrows, JetBrains Mono does.
+                ("→ ", FontId(1), Script::Common),
+                ("x", FontId(0), Script::Latin),
+            ]
+        );
+    }
+
+    #[test]
+    fn loading_a_file_keeps_the_io_error() {
+        let mut fonts = FontRegistry::new();
+        let err = fonts
+            .load_file("Missing", Weight::NORMAL, "res/missing.ttf")
+            .unwrap_err();
+
+        assert!(matches!(err, FontError::Io(err) if err.kind() == io::ErrorKind::NotFound));
+    }
+
+    #[test]
+    fn loading_a_file_twice_shares_its_bytes() {
+        let mut fonts = FontRegistry::new();
+        let path = "res/JetBrainsMono-Regular.ttf";
+        let first = fonts.load_file("Mono", Weight::NORMAL, path).unwrap();
+        let second = fonts.load_file("Mono", Weight::BOLD, path).unwrap();
+
+        assert_ne!(first, second);
+        assert_eq!(fonts.files.len(), 1);
+        assert_eq!(fonts.find("Mono", Weight::BOLD), second);
+    }
+
+    #[test]
+    fn fonts_loaded_at_runtime_can_be_picked_by_name() {
+        let mut fonts = FontRegistry::new();
+        let family = String::from("From config");
+        let bytes = fs::read("res/JetBrainsMono-Regular.ttf").unwrap();
+        let font = fonts.load_bytes(&family, Weight::NORMAL, bytes).unwrap();
+
+        let style = TextStyle::new(family, 16.0);
+        assert_eq!(fonts.find(&style.family, style.weight), font);
+    }
+
+    #[test]
+    fn metrics_add_up_to_the_height_freetype_has() {
+        let fonts = FontRegistry::new();
+        for size in [12.0, 18.0, 31.5] {
+            let metrics = fonts.metrics(FontId(0), size);
+            let height = fonts
+                .face(FontId(0), fixed_size(size))
+                .and_then(|face| face.size_metrics())
+                .unwrap()
+                .height as f32
+                / 64.0;
+
+            assert_eq!(metrics.line_height(), height);
+            assert!(metrics.ascender > 0.0 && metrics.descender < 0.0);
+        }
+    }
+
+    #[test]
+    fn line_height_multiplies_the_font_line_height() {
+        let fonts = FontRegistry::new();
+        let style = TextStyle::default();
+        let tall = style.clone().with_line_height(1.5);
+
+        assert_eq!(fonts.line_height(&tall), fonts.line_height(&style) * 1.5);
+    }
+
+    #[test]
+    fn baseline_splits_the_extra_space_evenly() {
+        let metrics = FontMetrics {
+            ascender: 15.0,
+            descender: -5.0,
+            line_gap: 2.0,
+        };
+
+        assert_eq!(metrics.baseline(metrics.line_height()), 16.0);
+        assert_eq!(metrics.baseline(30.0), 20.0);
+    }
+
+    #[test]
+    fn faces_that_cant_be_sized_fall_back_to_the_size() {
+        let fonts = FontRegistry::new();
+        // FreeType keeps pixel sizes in 16 bits, so this one wraps to 0.
+        let size = 65536.0;
+
+        assert!(fonts.face(FontId(0), fixed_size(size)).is_none());
+        assert_eq!(
+            fonts.metrics(FontId(0), size),
+            FontMetrics {
+                ascender: size,
+                descender: 0.0,
+                line_gap: 0.0,
+            }
+        );
+    }
+
+    #[test]
+    fn bad_fonts_are_an_error() {
+        let mut fonts = FontRegistry::new();
+        let err = fonts
+            .load_bytes("Bad", Weight::NORMAL, b"not a font")
+            .unwrap_err();
+
+        assert!(matches!(err, FontError::Font(_)));
+    }
+
+    #[test]
+    fn caret_positions_follow_the_shaped_text() {
+        let mut fonts = FontRegistry::new();
+        let style = TextStyle::default();
+        let text = "Wave fi";
+        let positions = fonts.caret_positions(text, &style);
+
+        assert_eq!(positions.len(), text.chars().count() + 1);
+        assert_eq!(positions[0], (0, 0.0));
+        assert_eq!(
+            positions.last(),
+            Some(&(text.len(), fonts.string_width(text, &style)))
+        );
+        // Kerning is included, so the "a" tucks in under the "W".
+        assert!(positions[1].1 < fonts.string_width("W", &style));
+        assert!(positions.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+    }
+
+    #[test]
+    fn right_to_left_text_is_drawn_in_visual_order() {
+        let fonts = FontRegistry::new();
+        let ab = glyph_ids(&fonts, "ab");
+
+        // The Hebrew comes first, so the line reads right to left and the
+        // Latin word ends up on the left.
+        let glyphs = glyph_ids(&fonts, "אב ab");
+        assert_eq!(glyphs.len(), 5);
+        assert_eq!(glyphs[..2], ab[..]);
+    }
+}
@@ -1,12 +1,28 @@
-use crate::renderer::State;
+use crate::{
+    app::App,
+    button::Button,
+    event::{
+        EventContext, EventStatus, KeyboardEvent, PointerEvent, PointerHandler, SCROLL_LINE_PIXELS,
+    },
+    focus::FocusManager,
+    font::TextStyle,
+    list::List,
+    quad::QuadStyle,
+    renderer::State,
+    scroll::Scroll,
+    text_input::TextInput,
+    wrap::{wrap_lines, Line},
+};
 use std::time::{Duration, Instant};
 use winit::{
-    event::{Event, WindowEvent},
+    dpi::PhysicalSize,
+    event::{ElementState, Event, KeyEvent, MouseScrollDelta, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
+    keyboard::ModifiersState,
     window::WindowBuilder,
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Bbox {
     pub min: (f32, f32),
     pub max: (f32, f32),
@@ -39,6 +55,45 @@ impl Bbox {
         (self.min.0, self.max.1)
     }
 
+    /// Move every edge outwards by `amount`.
+    pub fn expand(&self, amount: f32) -> Bbox {
+        Bbox::new(
+            self.min.0 - amount,
+            self.min.1 - amount,
+            self.max.0 + amount,
+            self.max.1 + amount,
+        )
+    }
+
+    /// Move the whole box by `x` and `y` without changing its size.
+    pub fn translate(&self, x: f32, y: f32) -> Bbox {
+        Bbox::new(
+            self.min.0 + x,
+            self.min.1 + y,
+            self.max.0 + x,
+            self.max.1 + y,
+        )
+    }
+
+    /// Move each edge inwards by the matching side of the spacing. The result
+    /// never has a negative width or height.
+    pub fn shrink(&self, spacing: &Spacing) -> Bbox {
+        let x0 = self.min.0 + spacing.left;
+        let y0 = self.min.1 + spacing.bottom;
+        let x1 = (self.max.0 - spacing.right).max(x0);
+        let y1 = (self.max.1 - spacing.top).max(y0);
+
+        Bbox::new(x0, y0, x1, y1)
+    }
+
+    /// The area covered by both boxes. Empty, but still positioned, if they
+    /// don't overlap.
+    pub fn intersect(&self, other: &Bbox) -> Bbox {
+        let min = (self.min.0.max(other.min.0), self.min.1.max(other.min.1));
+        let max = (self.max.0.min(other.max.0), self.max.1.min(other.max.1));
+        Bbox::new(min.0, min.1, max.0.max(min.0), max.1.max(min.1))
+    }
+
     // Returns true if pos is inside the bbox.
     pub fn inside(&self, pos: (f32, f32)) -> bool {
         let x_inside = self.min.0 <= pos.0 && pos.0 <= self.max.0;
@@ -48,165 +103,1053 @@ impl Bbox {
     }
 }
 
+/// Answers questions about the size of text while laying out. Kept separate
+/// from the renderers so a layout can be computed without a GPU.
+pub trait TextMeasure {
+    /// The horizontal space a string takes up when drawn on a single line.
+    fn string_width(&mut self, s: &str, style: &TextStyle) -> f32;
+
+    fn line_height(&self, style: &TextStyle) -> f32;
+
+    /// Where the caret goes at each char boundary of `s` drawn on a single
+    /// line, as (byte offset, x) pairs in the order of the text, from 0 to
+    /// `s.len()`.
+    ///
+    /// By default the widths of the chars are added up, which leaves out
+    /// kerning and ligatures.
+    fn caret_positions(&mut self, s: &str, style: &TextStyle) -> Vec<(usize, f32)> {
+        let mut x = 0.0;
+        let mut positions = Vec::with_capacity(s.len() + 1);
+        for (i, c) in s.char_indices() {
+            positions.push((i, x));
+            x += self.string_width(&s[i..i + c.len_utf8()], style);
+        }
+        positions.push((s.len(), x));
+        positions
+    }
+
+    /// The (width, height) of `text` drawn in `style`, along with its lines.
+    /// The text is wrapped to `max_width` if there is one, and always breaks
+    /// at newlines. Nothing is drawn or cached, so this is fine to call as
+    /// often as layout needs.
+    fn measure(
+        &mut self,
+        text: &str,
+        style: &TextStyle,
+        max_width: Option<f32>,
+    ) -> (f32, f32, Vec<Line>) {
+        let lines = wrap_lines(self, text, style, max_width);
+        let width = lines.iter().map(|line| line.width).fold(0.0, f32::max);
+        let height = lines.len() as f32 * self.line_height(style);
+        (width, height, lines)
+    }
+}
+
+/// The computed position of an element, along with the positions of its
+/// children in the same order as the element's children.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayoutNode {
+    /// The whole area of the element, not including its margin.
+    pub bbox: Bbox,
+    /// The bbox with the padding removed.
+    pub content: Bbox,
+    pub children: Vec<LayoutNode>,
+}
+
+impl LayoutNode {
+    /// Find the node for the descendant at `path`, a list of child indices.
+    pub fn find(&self, path: &[usize]) -> Option<&LayoutNode> {
+        match path.split_first() {
+            Some((&i, rest)) => self.children.get(i)?.find(rest),
+            None => Some(self),
+        }
+    }
+}
+
+/// Space around the four sides of a box, in pixels.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Spacing {
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub left: f32,
+}
+
+impl Spacing {
+    pub fn new(top: f32, right: f32, bottom: f32, left: f32) -> Self {
+        Self {
+            top,
+            right,
+            bottom,
+            left,
+        }
+    }
+
+    /// The same spacing on every side.
+    pub fn all(amount: f32) -> Self {
+        Self::new(amount, amount, amount, amount)
+    }
+
+    /// `horizontal` on the left and right, `vertical` on the top and bottom.
+    pub fn symmetric(horizontal: f32, vertical: f32) -> Self {
+        Self::new(vertical, horizontal, vertical, horizontal)
+    }
+
+    pub fn horizontal(&self) -> f32 {
+        self.left + self.right
+    }
+
+    pub fn vertical(&self) -> f32 {
+        self.top + self.bottom
+    }
+}
+
+/// How much space an element takes up along one axis of its parent.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Size {
+    /// An exact number of pixels.
+    Fixed(f32),
+    /// A percentage (0 to 100) of the parent's size.
+    Percent(f32),
+    /// A share of whatever space is left over once every other child has been
+    /// sized, weighted against the other flex children.
+    Flex(f32),
+    /// Just big enough to hold the content.
+    Fit,
+}
+
+impl Default for Size {
+    fn default() -> Self {
+        Size::Flex(1.0)
+    }
+}
+
+/// How a container spreads its children along its main axis when they don't
+/// take up all of the space.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Justify {
+    #[default]
+    Start,
+    Center,
+    End,
+    /// Even space between children, none before the first or after the last.
+    SpaceBetween,
+    /// Even space around each child, so the ends get half as much as the gaps.
+    SpaceAround,
+}
+
+/// How a container places its children on its cross axis.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Align {
+    Start,
+    Center,
+    End,
+    /// Fill the whole cross axis, ignoring the child's own size.
+    #[default]
+    Stretch,
+}
+
+/// Where text sits inside of its box along one axis.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TextAlign {
+    #[default]
+    Start,
+    Center,
+    End,
+}
+
+impl TextAlign {
+    /// How far into `free_space` the text should start.
+    fn offset(&self, free_space: f32) -> f32 {
+        match self {
+            TextAlign::Start => 0.0,
+            TextAlign::Center => free_space / 2.0,
+            TextAlign::End => free_space,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum Thing {
     Text {
         text: String,
+        style: TextStyle,
         text_color: [f32; 4],
         background_color: [f32; 4],
+        /// (horizontal, vertical) placement inside the content box.
+        align: (TextAlign, TextAlign),
     },
     TextMultiLine {
         text: Vec<String>,
+        style: TextStyle,
         wrap_text: bool,
         text_color: [f32; 4],
         background_color: [f32; 4],
     },
     Quad {
-        color: [f32; 4],
+        style: QuadStyle,
     },
     TexturedQuad {},
+    Button(Button),
+    TextInput(TextInput),
 
     Hbox(Hbox),
     Vbox(Vbox),
+    Scroll(Scroll),
+    List(List),
+}
+
+/// A child of a container. Wraps a thing with the space it asks for.
+///
+/// The margin is empty space outside of the element, and is not counted as
+/// part of its width or height. The padding is space inside the element
+/// between its edges and its content.
+#[derive(Debug)]
+pub struct Element {
+    pub thing: Thing,
+    pub width: Size,
+    pub height: Size,
+    pub margin: Spacing,
+    pub padding: Spacing,
+    /// Drawn behind the thing, filling the element.
+    pub background: Option<QuadStyle>,
+    /// Cut off anything drawn by the thing or its children that falls outside
+    /// of the element.
+    pub clip: bool,
+    pub on_pointer: Option<PointerHandler>,
+}
+
+impl Element {
+    pub fn new(thing: Thing) -> Self {
+        Self {
+            thing,
+            width: Size::default(),
+            height: Size::default(),
+            margin: Spacing::default(),
+            padding: Spacing::default(),
+            background: None,
+            clip: false,
+            on_pointer: None,
+        }
+    }
+
+    pub fn with_width(mut self, width: Size) -> Self {
+        self.width = width;
+        self
+    }
+
+    pub fn with_height(mut self, height: Size) -> Self {
+        self.height = height;
+        self
+    }
+
+    pub fn with_margin(mut self, margin: Spacing) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    pub fn with_padding(mut self, padding: Spacing) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    pub fn with_background(mut self, background: impl Into<QuadStyle>) -> Self {
+        self.background = Some(background.into());
+        self
+    }
+
+    pub fn with_clip(mut self, clip: bool) -> Self {
+        self.clip = clip;
+        self
+    }
+
+    /// Run `handler` for pointer events over this element that none of its
+    /// children handled.
+    pub fn with_on_pointer(
+        mut self,
+        handler: impl FnMut(&mut Thing, &PointerEvent) -> EventStatus + 'static,
+    ) -> Self {
+        self.on_pointer = Some(PointerHandler::new(handler));
+        self
+    }
+
+    /// Compute where this element and all of its children go when given
+    /// `bbox`. Nothing is drawn.
+    pub fn layout(&self, measure: &mut dyn TextMeasure, bbox: Bbox) -> LayoutNode {
+        let content = bbox.shrink(&self.padding);
+        let children = match &self.thing {
+            Thing::Hbox(hbox) => hbox.layout(measure, content),
+            Thing::Vbox(vbox) => vbox.layout(measure, content),
+            Thing::Scroll(scroll) => scroll.layout(measure, content),
+            Thing::List(list) => list.layout(measure, content),
+            _ => vec![],
+        };
+
+        LayoutNode {
+            bbox,
+            content,
+            children,
+        }
+    }
+
+    /// Draw this element using a layout previously computed by `layout`.
+    pub fn draw(&self, state: &mut State, node: &LayoutNode) {
+        if let Some(background) = &self.background {
+            state.add_quad(background.clone(), &node.bbox);
+        }
+
+        if self.clip {
+            state.push_clip(&node.bbox);
+            self.thing.draw(state, node);
+            state.pop_clip();
+        } else {
+            self.thing.draw(state, node);
+        }
+    }
+
+    /// Send a pointer event to the deepest element under the pointer, then
+    /// bubble it back up through the parents until one of them handles it.
+    ///
+    /// Returns the path of child indices from this element to the element
+    /// that handled the event, if any did.
+    pub fn dispatch_pointer(
+        &mut self,
+        node: &LayoutNode,
+        event: &PointerEvent,
+        cx: &mut EventContext,
+    ) -> Option<Vec<usize>> {
+        let pos = event.pos();
+        if !node.bbox.inside(pos) {
+            return None;
+        }
+
+        // Scrollbars are drawn over what they scroll, and what they scroll is
+        // cut off at the edges of the content.
+        let over_children = match &self.thing {
+            Thing::Scroll(scroll) => node.content.inside(pos) && !scroll.on_scrollbar(node, pos),
+            Thing::List(list) => node.content.inside(pos) && !list.on_scrollbar(node, pos),
+            _ => true,
+        };
+
+        if let Some(elements) = self.thing.elements_mut().filter(|_| over_children) {
+            // Later children are drawn on top, so they get the first look.
+            let hit = elements
+                .iter_mut()
+                .zip(&node.children)
+                .enumerate()
+                .rev()
+                .find(|(_, (_, child))| child.bbox.inside(pos));
+            if let Some((i, (elem, child))) = hit {
+                if let Some(mut path) = elem.dispatch_pointer(child, event, cx) {
+                    path.insert(0, i);
+                    return Some(path);
+                }
+            }
+        }
+
+        match self.handle_pointer(node, event, cx) {
+            EventStatus::Handled => Some(vec![]),
+            EventStatus::Ignored => None,
+        }
+    }
+
+    /// Give this element alone a chance to handle a pointer event. Widgets get
+    /// the first look, then the element's own handler.
+    pub fn handle_pointer(
+        &mut self,
+        node: &LayoutNode,
+        event: &PointerEvent,
+        cx: &mut EventContext,
+    ) -> EventStatus {
+        let status = match &mut self.thing {
+            Thing::Button(button) => button.handle_pointer(node, event),
+            Thing::TextInput(input) => input.handle_pointer(node, event, cx),
+            Thing::Scroll(scroll) => scroll.handle_pointer(node, event),
+            Thing::List(list) => list.handle_pointer(node, event),
+            _ => EventStatus::Ignored,
+        };
+        if status == EventStatus::Handled {
+            return status;
+        }
+
+        match &mut self.on_pointer {
+            Some(handler) => handler.call(&mut self.thing, event),
+            None => EventStatus::Ignored,
+        }
+    }
+
+    /// Send a key press to this element.
+    pub fn handle_key(&mut self, event: &KeyboardEvent) -> EventStatus {
+        match &mut self.thing {
+            Thing::Button(button) => button.handle_key(event),
+            Thing::TextInput(input) => input.handle_key(event),
+            _ => EventStatus::Ignored,
+        }
+    }
+
+    /// Build the rows of every `List` that scrolled since `node` was laid
+    /// out, or drop the ones that scrolled away. Returns whether anything
+    /// changed, in which case the layout is out of date.
+    ///
+    /// `path` is the path to this element. The paths in `tracked`, such as the
+    /// focused element, are moved along with the rows they lead into, or
+    /// cleared if their row was dropped.
+    pub(crate) fn update_rows(
+        &mut self,
+        measure: &mut dyn TextMeasure,
+        node: &LayoutNode,
+        path: &mut Vec<usize>,
+        tracked: &mut [&mut Option<Vec<usize>>],
+    ) -> bool {
+        // The list's own rows are laid out again before looking inside of them.
+        if let Thing::List(list) = &mut self.thing {
+            if list.update_rows(measure, node, path, tracked) {
+                return true;
+            }
+        }
+
+        let mut changed = false;
+        if let Some(elements) = self.thing.elements_mut() {
+            for (i, (elem, child)) in elements.iter_mut().zip(&node.children).enumerate() {
+                path.push(i);
+                changed |= elem.update_rows(measure, child, path, tracked);
+                path.pop();
+            }
+        }
+        changed
+    }
+
+    /// Scroll every `Scroll` between this element and the descendant at `path`
+    /// so that the descendant is visible. `node` is this element's layout, the
+    /// new positions take effect the next time it's laid out.
+    pub fn scroll_into_view(&mut self, node: &LayoutNode, path: &[usize]) {
+        self.reveal(node, path);
+    }
+
+    /// Returns where the descendant at `path` ends up once the scrolls inside
+    /// of this element have moved it.
+    fn reveal(&mut self, node: &LayoutNode, path: &[usize]) -> Option<Bbox> {
+        let Some((&i, rest)) = path.split_first() else {
+            return Some(node.bbox);
+        };
+        let child = node.children.get(i)?;
+        let target = self.thing.elements_mut()?.get_mut(i)?.reveal(child, rest)?;
+
+        // Scrolls further in have already moved the target, so each one only
+        // has to make up the difference.
+        match &mut self.thing {
+            Thing::Scroll(scroll) => {
+                let (x, y) = scroll.scroll_into_view(node, &target);
+                Some(target.translate(x, y))
+            }
+            Thing::List(list) => {
+                let (x, y) = list.scroll_into_view(node, &target);
+                Some(target.translate(x, y))
+            }
+            _ => Some(target),
+        }
+    }
+
+    /// Find the descendant at `path`, as returned by `dispatch_pointer`.
+    pub fn find_mut(&mut self, path: &[usize]) -> Option<&mut Element> {
+        match path.split_first() {
+            Some((&i, rest)) => self.thing.elements_mut()?.get_mut(i)?.find_mut(rest),
+            None => Some(self),
+        }
+    }
+
+    /// The area the descendant at `path` is clipped to by the elements above
+    /// it, or `None` if none of them clip. The descendant's own clip isn't
+    /// included, only what it's drawn inside of.
+    pub(crate) fn clip_of(&self, node: &LayoutNode, path: &[usize]) -> Option<Bbox> {
+        let (&i, rest) = path.split_first()?;
+
+        // The same clips `draw` pushes before drawing the children.
+        let mut clip = self.clip.then_some(node.bbox);
+        if let Thing::Scroll(_) | Thing::List(_) = self.thing {
+            clip = Some(clip.map_or(node.content, |clip| clip.intersect(&node.content)));
+        }
+
+        let inner = match (self.thing.elements(), node.children.get(i)) {
+            (Some(elements), Some(child)) => elements.get(i)?.clip_of(child, rest),
+            _ => None,
+        };
+        match (clip, inner) {
+            (Some(clip), Some(inner)) => Some(clip.intersect(&inner)),
+            (clip, inner) => clip.or(inner),
+        }
+    }
+
+    /// Add the paths of every focusable element in this subtree to `out`, in
+    /// layout order. `path` is the path to this element.
+    pub(crate) fn collect_focusable(&self, path: &mut Vec<usize>, out: &mut Vec<Vec<usize>>) {
+        if self.thing.focusable() {
+            out.push(path.clone());
+        }
+
+        if let Some(elements) = self.thing.elements() {
+            for (i, elem) in elements.iter().enumerate() {
+                path.push(i);
+                elem.collect_focusable(path, out);
+                path.pop();
+            }
+        }
+    }
+
+    /// Let every widget know whether the pointer is over it. Widgets clipped
+    /// away by a parent aren't `visible` under the pointer, even if their bbox
+    /// is.
+    pub(crate) fn update_hover(&mut self, node: &LayoutNode, pos: (f32, f32), visible: bool) {
+        let inside = visible && node.bbox.inside(pos);
+        if let Thing::Button(button) = &mut self.thing {
+            button.set_hovered(inside);
+        }
+
+        let visible = match self.thing {
+            Thing::Scroll(_) | Thing::List(_) => visible && node.content.inside(pos),
+            _ if self.clip => inside,
+            _ => visible,
+        };
+        if let Some(elements) = self.thing.elements_mut() {
+            for (elem, child) in elements.iter_mut().zip(&node.children) {
+                elem.update_hover(child, pos, visible);
+            }
+        }
+    }
+
+    /// The size of this element when it is sized to fit its content. Includes
+    /// the padding but not the margin. `max_width` is the widest the element
+    /// can be, which wrapped text needs to know how tall it is.
+    pub(crate) fn fit_size(
+        &self,
+        measure: &mut dyn TextMeasure,
+        max_width: Option<f32>,
+    ) -> (f32, f32) {
+        let max_width = match self.width {
+            Size::Fixed(width) => Some(width),
+            _ => max_width,
+        };
+        let max_content_width = max_width.map(|width| (width - self.padding.horizontal()).max(0.0));
+        let content = self.thing.fit_size(measure, max_content_width);
+        let width = match self.width {
+            Size::Fixed(width) => width,
+            _ => content.0 + self.padding.horizontal(),
+        };
+        let height = match self.height {
+            Size::Fixed(height) => height,
+            _ => content.1 + self.padding.vertical(),
+        };
+
+        (width, height)
+    }
+}
+
+impl From<Thing> for Element {
+    fn from(thing: Thing) -> Self {
+        Self::new(thing)
+    }
 }
 
 #[derive(Debug)]
 pub struct Hbox {
-    elements: Vec<Thing>,
+    elements: Vec<Element>,
+    gap: f32,
+    justify: Justify,
+    align: Align,
 }
 
 impl Hbox {
-    pub fn new(elements: Vec<Thing>) -> Self {
-        Self { elements }
+    pub fn new(elements: Vec<Element>) -> Self {
+        Self {
+            elements,
+            gap: 0.0,
+            justify: Justify::default(),
+            align: Align::default(),
+        }
+    }
+
+    /// How children are spread along the main axis when they don't fill it.
+    pub fn with_justify(mut self, justify: Justify) -> Self {
+        self.justify = justify;
+        self
+    }
+
+    /// How children are placed on the cross axis.
+    pub fn with_align(mut self, align: Align) -> Self {
+        self.align = align;
+        self
+    }
+
+    /// Leave `gap` pixels of space between each child.
+    pub fn with_gap(mut self, gap: f32) -> Self {
+        self.gap = gap;
+        self
     }
 }
 
 #[derive(Debug)]
 pub struct Vbox {
-    elements: Vec<Thing>,
+    elements: Vec<Element>,
+    gap: f32,
+    justify: Justify,
+    align: Align,
 }
 
 impl Vbox {
-    pub fn new(elements: Vec<Thing>) -> Self {
-        Self { elements }
+    pub fn new(elements: Vec<Element>) -> Self {
+        Self {
+            elements,
+            gap: 0.0,
+            justify: Justify::default(),
+            align: Align::default(),
+        }
+    }
+
+    /// How children are spread along the main axis when they don't fill it.
+    pub fn with_justify(mut self, justify: Justify) -> Self {
+        self.justify = justify;
+        self
+    }
+
+    /// How children are placed on the cross axis.
+    pub fn with_align(mut self, align: Align) -> Self {
+        self.align = align;
+        self
+    }
+
+    /// Leave `gap` pixels of space between each child.
+    pub fn with_gap(mut self, gap: f32) -> Self {
+        self.gap = gap;
+        self
+    }
+}
+
+impl Thing {
+    /// Backgrounds fill the node's bbox, everything else is placed inside of
+    /// its content.
+    fn draw(&self, state: &mut State, node: &LayoutNode) {
+        let bbox = node.bbox;
+        let content = node.content;
+        let top_left = content.top_left();
+
+        match self {
+            Thing::Text {
+                text,
+                style,
+                text_color,
+                background_color,
+                align,
+            } => {
+                state.add_quad(*background_color, &bbox);
+
+                let width = state.text_renderer.string_width(text, style);
+                let height = state.text_renderer.line_height(style);
+                let x = top_left.0 + align.0.offset(content.width() - width);
+                let y = top_left.1 - align.1.offset(content.height() - height);
+                state.add_text(text, style, x, y, *text_color, None);
+            }
+            Thing::TextMultiLine {
+                text,
+                style,
+                wrap_text,
+                text_color,
+                background_color,
+            } => {
+                state.add_quad(*background_color, &bbox);
+
+                state.add_multiline_text(
+                    text,
+                    style,
+                    top_left.0,
+                    top_left.1,
+                    *text_color,
+                    if *wrap_text { Some(&content) } else { None },
+                );
+            }
+            Thing::Quad { style } => state.add_quad(style.clone(), &bbox),
+            Thing::TexturedQuad {} => state.add_textured_quad(&bbox),
+            Thing::Button(button) => button.draw(state, node),
+            Thing::TextInput(input) => input.draw(state, node),
+            Thing::Hbox(hbox) => hbox.draw(state, node),
+            Thing::Vbox(vbox) => vbox.draw(state, node),
+            Thing::Scroll(scroll) => scroll.draw(state, node),
+            Thing::List(list) => list.draw(state, node),
+        }
+    }
+
+    /// Whether the thing takes keyboard input.
+    pub(crate) fn focusable(&self) -> bool {
+        match self {
+            Thing::Button(button) => !button.disabled,
+            Thing::TextInput(_) => true,
+            _ => false,
+        }
+    }
+
+    pub(crate) fn set_focused(&mut self, focused: bool) {
+        if let Thing::TextInput(input) = self {
+            input.set_focused(focused);
+        }
+    }
+
+    fn elements(&self) -> Option<&Vec<Element>> {
+        match self {
+            Thing::Hbox(hbox) => Some(hbox.elements()),
+            Thing::Vbox(vbox) => Some(vbox.elements()),
+            Thing::Scroll(scroll) => Some(scroll.elements()),
+            Thing::List(list) => Some(list.elements()),
+            _ => None,
+        }
+    }
+
+    fn elements_mut(&mut self) -> Option<&mut Vec<Element>> {
+        match self {
+            Thing::Hbox(hbox) => Some(hbox.elements_mut()),
+            Thing::Vbox(vbox) => Some(vbox.elements_mut()),
+            Thing::Scroll(scroll) => Some(scroll.elements_mut()),
+            Thing::List(list) => Some(list.elements_mut()),
+            _ => None,
+        }
+    }
+
+    /// The natural (width, height) of the content, wrapping text to
+    /// `max_width` where it wraps. Things without any content of their own,
+    /// like quads, have no natural size.
+    fn fit_size(&self, measure: &mut dyn TextMeasure, max_width: Option<f32>) -> (f32, f32) {
+        match self {
+            Thing::Text { text, style, .. } => {
+                let (width, height, _) = measure.measure(text, style, None);
+                (width, height)
+            }
+            Thing::TextMultiLine {
+                text,
+                style,
+                wrap_text,
+                ..
+            } => {
+                let max_width = if *wrap_text { max_width } else { None };
+                let mut width: f32 = 0.0;
+                let mut height = 0.0;
+                for line in text {
+                    let (line_width, line_height, _) = measure.measure(line, style, max_width);
+                    width = width.max(line_width);
+                    height += line_height;
+                }
+                (width, height)
+            }
+            Thing::Quad { .. } | Thing::TexturedQuad {} => (0.0, 0.0),
+            Thing::Button(button) => button.fit_size(measure),
+            Thing::TextInput(input) => input.fit_size(measure),
+            Thing::Hbox(hbox) => hbox.fit_size(measure, max_width),
+            Thing::Vbox(vbox) => vbox.fit_size(measure, max_width),
+            Thing::Scroll(scroll) => scroll.fit_size(measure, max_width),
+            // A list is only ever as big as it's made.
+            Thing::List(_) => (0.0, 0.0),
+        }
     }
 }
+
 trait Container {
-    fn layout(&self, state: &mut State, parent_size: Bbox) {
-        for (i, elem) in self.elements().iter().enumerate() {
+    fn layout(&self, measure: &mut dyn TextMeasure, parent_size: Bbox) -> Vec<LayoutNode> {
+        let (sizes, free_space) = self.main_axis_sizes(measure, &parent_size);
+
+        // Spread whatever the children didn't use according to the justify mode.
+        let count = self.elements().len() as f32;
+        let (mut offset, spacing) = match self.justify() {
+            Justify::Start => (0.0, 0.0),
+            Justify::Center => (free_space / 2.0, 0.0),
+            Justify::End => (free_space, 0.0),
+            Justify::SpaceBetween if count > 1.0 => (0.0, free_space / (count - 1.0)),
+            Justify::SpaceBetween => (0.0, 0.0),
+            Justify::SpaceAround => (free_space / (2.0 * count), free_space / count),
+        };
+
+        // Children are placed left to right in an hbox and top to bottom in a vbox.
+        let mut children = Vec::with_capacity(self.elements().len());
+        for (elem, size) in self.elements().iter().zip(sizes) {
+            let margin = &elem.margin;
             let child_bbox = if self.is_hbox() {
-                let child_index = i;
-                let child_width = parent_size.width() / self.elements().len() as f32;
-                let x0 = parent_size.min.0 + child_width * child_index as f32;
+                let x0 = parent_size.min.0 + offset + margin.left;
+                offset += margin.left + size + margin.right + self.gap() + spacing;
 
-                Bbox::new(x0, parent_size.min.1, x0 + child_width, parent_size.max.1)
+                let slot_y0 = parent_size.min.1 + margin.bottom;
+                let slot_y1 = (parent_size.max.1 - margin.top).max(slot_y0);
+                let (y0, y1) = self.align_cross_axis(measure, elem, size, slot_y0, slot_y1);
+                Bbox::new(x0, y0, x0 + size, y1)
             } else {
-                let child_index = self.elements().len() - i - 1;
-                let child_height = parent_size.height() / self.elements().len() as f32;
-                let y0 = parent_size.min.1 + child_height * child_index as f32;
+                let y1 = parent_size.max.1 - offset - margin.top;
+                offset += margin.top + size + margin.bottom + self.gap() + spacing;
 
-                Bbox::new(parent_size.min.0, y0, parent_size.max.0, y0 + child_height)
+                let slot_x0 = parent_size.min.0 + margin.left;
+                let slot_x1 = (parent_size.max.0 - margin.right).max(slot_x0);
+                let (x0, x1) = self.align_cross_axis(measure, elem, size, slot_x0, slot_x1);
+                Bbox::new(x0, y1 - size, x1, y1)
             };
 
-            let top_left = child_bbox.top_left();
+            children.push(elem.layout(measure, child_bbox));
+        }
 
-            match elem {
-                Thing::Text {
-                    text,
-                    text_color,
-                    background_color,
-                } => {
-                    state
-                        .quad_renderer
-                        .add_instance(*background_color, &child_bbox);
-                    state.text_renderer.add_string_to_batch(
-                        text,
-                        &state.queue,
-                        top_left.0,
-                        top_left.1,
-                        *text_color,
-                        None,
-                    );
+        children
+    }
+
+    fn draw(&self, state: &mut State, node: &LayoutNode) {
+        for (elem, child) in self.elements().iter().zip(&node.children) {
+            elem.draw(state, child);
+        }
+    }
+
+    /// Resolve each child's size along the main axis. Margins and gaps are
+    /// taken out first, then fixed, percent and fit children are sized, and
+    /// any space left over is split between the flex children by weight.
+    ///
+    /// Also returns the space nobody claimed, which is only non zero when there
+    /// are no flex children.
+    fn main_axis_sizes(
+        &self,
+        measure: &mut dyn TextMeasure,
+        parent_size: &Bbox,
+    ) -> (Vec<f32>, f32) {
+        let available = if self.is_hbox() {
+            parent_size.width()
+        } else {
+            parent_size.height()
+        };
+
+        let mut sizes = Vec::with_capacity(self.elements().len());
+        let mut used = self.total_gap();
+        let mut total_weight = 0.0;
+        for elem in self.elements() {
+            used += self.main_axis_margin(elem);
+
+            let size = match self.main_axis(elem) {
+                Size::Fixed(size) => size,
+                Size::Percent(percent) => available * percent / 100.0,
+                Size::Fit if self.is_hbox() => elem.fit_size(measure, None).0,
+                Size::Fit => {
+                    let max_width = (parent_size.width() - elem.margin.horizontal()).max(0.0);
+                    elem.fit_size(measure, Some(max_width)).1
                 }
-                Thing::TextMultiLine {
-                    text,
-                    wrap_text,
-                    text_color,
-                    background_color,
-                } => {
-                    state
-                        .quad_renderer
-                        .add_instance(*background_color, &child_bbox);
-
-                    state.text_renderer.add_multiline_string_to_batch(
-                        text,
-                        &state.queue,
-                        top_left.0,
-                        top_left.1,
-                        *text_color,
-                        if *wrap_text { Some(&child_bbox) } else { None },
-                    );
+                Size::Flex(weight) => {
+                    total_weight += weight;
+                    0.0
+                }
+            };
+            used += size;
+            sizes.push(size);
+        }
+
+        let leftover = (available - used).max(0.0);
+        if total_weight > 0.0 {
+            for (elem, size) in self.elements().iter().zip(sizes.iter_mut()) {
+                if let Size::Flex(weight) = self.main_axis(elem) {
+                    *size = leftover * weight / total_weight;
                 }
-                Thing::Quad { color } => state.quad_renderer.add_instance(*color, &child_bbox),
-                Thing::TexturedQuad {} => state.textured_quad_renderer.add_instance(&child_bbox),
-                Thing::Hbox(hbox) => hbox.layout(state, child_bbox),
-                Thing::Vbox(vbox) => vbox.layout(state, child_bbox),
             }
+            return (sizes, 0.0);
         }
+
+        (sizes, leftover)
     }
 
-    fn elements(&self) -> &Vec<Thing>;
+    /// Place a child inside the span [start, end] of the cross axis, returning
+    /// the span it actually covers. A stretched child always fills the span,
+    /// otherwise the child's own cross axis size is used, never growing past
+    /// the span. Start is the top of an hbox and the left of a vbox.
+    ///
+    /// `main_size` is the size the child already got along the main axis.
+    fn align_cross_axis(
+        &self,
+        measure: &mut dyn TextMeasure,
+        elem: &Element,
+        main_size: f32,
+        start: f32,
+        end: f32,
+    ) -> (f32, f32) {
+        let available = end - start;
+        let cross = if self.is_hbox() {
+            elem.height
+        } else {
+            elem.width
+        };
+
+        let size = match (self.align(), cross) {
+            (Align::Stretch, _) | (_, Size::Flex(_)) => available,
+            (_, Size::Fixed(size)) => size,
+            (_, Size::Percent(percent)) => available * percent / 100.0,
+            (_, Size::Fit) if self.is_hbox() => elem.fit_size(measure, Some(main_size)).1,
+            (_, Size::Fit) => elem.fit_size(measure, Some(available)).0,
+        }
+        .min(available);
+
+        let free_space = available - size;
+        let before = match self.align() {
+            Align::Start | Align::Stretch => 0.0,
+            Align::Center => free_space / 2.0,
+            Align::End => free_space,
+        };
+
+        // The y axis points up, so the start of an hbox is at the end of the span.
+        if self.is_hbox() {
+            (end - before - size, end - before)
+        } else {
+            (start + before, start + before + size)
+        }
+    }
+
+    /// The natural size of the container: children laid end to end along the
+    /// main axis, and as big as the biggest child on the cross axis. Children
+    /// of a vbox are no wider than `max_width`, an hbox doesn't limit them.
+    fn fit_size(&self, measure: &mut dyn TextMeasure, max_width: Option<f32>) -> (f32, f32) {
+        let mut main: f32 = self.total_gap();
+        let mut cross: f32 = 0.0;
+        for elem in self.elements() {
+            let max_width = match max_width {
+                Some(width) if !self.is_hbox() => Some((width - elem.margin.horizontal()).max(0.0)),
+                _ => None,
+            };
+            let (width, height) = elem.fit_size(measure, max_width);
+            let (elem_main, elem_cross) = if self.is_hbox() {
+                (
+                    width + elem.margin.horizontal(),
+                    height + elem.margin.vertical(),
+                )
+            } else {
+                (
+                    height + elem.margin.vertical(),
+                    width + elem.margin.horizontal(),
+                )
+            };
+            main += elem_main;
+            cross = cross.max(elem_cross);
+        }
+
+        if self.is_hbox() {
+            (main, cross)
+        } else {
+            (cross, main)
+        }
+    }
+
+    fn total_gap(&self) -> f32 {
+        self.gap() * self.elements().len().saturating_sub(1) as f32
+    }
+
+    fn main_axis_margin(&self, elem: &Element) -> f32 {
+        if self.is_hbox() {
+            elem.margin.horizontal()
+        } else {
+            elem.margin.vertical()
+        }
+    }
+
+    fn main_axis(&self, elem: &Element) -> Size {
+        if self.is_hbox() {
+            elem.width
+        } else {
+            elem.height
+        }
+    }
+
+    fn elements(&self) -> &Vec<Element>;
+
+    fn elements_mut(&mut self) -> &mut Vec<Element>;
+
+    fn gap(&self) -> f32;
+
+    fn justify(&self) -> Justify;
+
+    fn align(&self) -> Align;
 
     fn is_hbox(&self) -> bool;
 }
 
 impl Container for Hbox {
-    fn elements(&self) -> &Vec<Thing> {
+    fn elements(&self) -> &Vec<Element> {
         &self.elements
     }
 
+    fn elements_mut(&mut self) -> &mut Vec<Element> {
+        &mut self.elements
+    }
+
+    fn gap(&self) -> f32 {
+        self.gap
+    }
+
+    fn justify(&self) -> Justify {
+        self.justify
+    }
+
+    fn align(&self) -> Align {
+        self.align
+    }
+
     fn is_hbox(&self) -> bool {
         true
     }
 }
 
 impl Container for Vbox {
-    fn elements(&self) -> &Vec<Thing> {
+    fn elements(&self) -> &Vec<Element> {
         &self.elements
     }
 
+    fn elements_mut(&mut self) -> &mut Vec<Element> {
+        &mut self.elements
+    }
+
+    fn gap(&self) -> f32 {
+        self.gap
+    }
+
+    fn justify(&self) -> Justify {
+        self.justify
+    }
+
+    fn align(&self) -> Align {
+        self.align
+    }
+
     fn is_hbox(&self) -> bool {
         false
     }
 }
 
 pub struct SceneRoot<'window> {
-    root: Box<dyn Container>,
+    root: Element,
+    /// The layout from the last frame, used to find what is under the pointer.
+    layout: Option<LayoutNode>,
+    cursor: (f32, f32),
+    /// The element that handled the last press. It gets the moves, presses and
+    /// releases until the button is released, even once the pointer has left
+    /// it.
+    captured: Option<Vec<usize>>,
+    focus: FocusManager,
+    modifiers: ModifiersState,
 
     state: State<'window>,
     last_frame_time: Duration,
 }
 
 impl<'window> SceneRoot<'window> {
-    pub async fn run() {
+    pub async fn run(app: App) {
         let event_loop = EventLoop::new().unwrap();
-        let window = WindowBuilder::new().build(&event_loop).unwrap();
+        let window = WindowBuilder::new()
+            .with_title(app.window.title)
+            .with_inner_size(PhysicalSize::new(app.window.width, app.window.height))
+            .with_resizable(app.window.resizable)
+            .build(&event_loop)
+            .unwrap();
         event_loop.set_control_flow(ControlFlow::Poll);
 
         let mut scene_root = SceneRoot {
-            root: Box::new(Hbox::new(vec![Thing::TextMultiLine {
-                text: vec![
-                    "ASDF KJASDF KJFJDFJ DFJ AJFADFDSF JSJDF JASDF KJASDF KJFJDFJ DFJ AJDSF JSJDF J".to_string(),
-                    "KJFJDFJ DFJ AJDSF JSJDF JASDF KVDVDVSJASDFKJFJDFJ DFJ AJDSF JSJDF JASDF KJASDF".to_string(),
-                    "ASDF KJASDF JSJDF JKJFJDFJ DFJ AJDSFASDF KJASDFVADSVZXC JSJDF JKJFJDFJ DFJ AJDSF".to_string(),
-                    "AJDSF JSJDFXC JASDF KJASDF KJFJDFVCZX DFJAJDSF JSJDF JASDF KJASDF KJFJDFJ DFJ".to_string(),
-                    "ASDF AJDSF JSJDF JKJASDF KJFJDFXZCVZCXVJ DFJASDF AJDSF JSJDF JKJASDF KJFJDFJ DFJ".to_string(),
-                ],
-                wrap_text : true,
-                text_color: [1.0, 0.0, 0.0, 1.0],
-                background_color: [0.0, 0.0, 0.0, 1.0],
-            }])),
-            state: State::new(&window).await,
+            root: app.root,
+            layout: None,
+            cursor: (0.0, 0.0),
+            captured: None,
+            focus: FocusManager::default(),
+            modifiers: ModifiersState::empty(),
+            state: State::new(&window, app.fonts).await,
             last_frame_time: Duration::from_nanos(0),
         };
 
@@ -224,6 +1167,70 @@ impl<'window> SceneRoot<'window> {
                 } => {
                     scene_root.state.resize(size);
                 }
+                Event::WindowEvent {
+                    event: WindowEvent::CursorMoved { position, .. },
+                    ..
+                } => {
+                    // winit measures from the top left, jui from the bottom left.
+                    scene_root.cursor = (
+                        position.x as f32,
+                        scene_root.state.size.height as f32 - position.y as f32,
+                    );
+                    scene_root.dispatch_pointer(PointerEvent::Moved {
+                        pos: scene_root.cursor,
+                    });
+                }
+                Event::WindowEvent {
+                    event: WindowEvent::MouseInput { state, button, .. },
+                    ..
+                } => {
+                    let pos = scene_root.cursor;
+                    scene_root.dispatch_pointer(match state {
+                        ElementState::Pressed => PointerEvent::Pressed { pos, button },
+                        ElementState::Released => PointerEvent::Released { pos, button },
+                    });
+                }
+                Event::WindowEvent {
+                    event: WindowEvent::MouseWheel { delta, .. },
+                    ..
+                } => {
+                    let delta = match delta {
+                        MouseScrollDelta::LineDelta(x, y) => {
+                            (x * SCROLL_LINE_PIXELS, y * SCROLL_LINE_PIXELS)
+                        }
+                        MouseScrollDelta::PixelDelta(delta) => (delta.x as f32, delta.y as f32),
+                    };
+                    scene_root.dispatch_pointer(PointerEvent::Scrolled {
+                        pos: scene_root.cursor,
+                        delta,
+                    });
+                }
+                Event::WindowEvent {
+                    event: WindowEvent::ModifiersChanged(modifiers),
+                    ..
+                } => {
+                    scene_root.modifiers = modifiers.state();
+                }
+                Event::WindowEvent {
+                    event:
+                        WindowEvent::KeyboardInput {
+                            event:
+                                KeyEvent {
+                                    logical_key,
+                                    text,
+                                    state: ElementState::Pressed,
+                                    ..
+                                },
+                            ..
+                        },
+                    ..
+                } => {
+                    scene_root.dispatch_key(KeyboardEvent {
+                        key: logical_key,
+                        text: text.map(|text| text.to_string()),
+                        modifiers: scene_root.modifiers,
+                    });
+                }
                 Event::WindowEvent {
                     event: WindowEvent::RedrawRequested,
                     ..
@@ -257,15 +1264,462 @@ impl<'window> SceneRoot<'window> {
     pub fn update(&mut self) {
         self.state.clear();
         let window_size = self.state.window.inner_size();
-        self.root.layout(
-            &mut self.state,
-            Bbox::new(
-                0.0,
-                0.0,
-                window_size.width as f32,
-                window_size.height as f32,
-            ),
+        let window_bbox = Bbox::new(
+            0.0,
+            0.0,
+            window_size.width as f32,
+            window_size.height as f32,
         );
+        let bbox = window_bbox.shrink(&self.root.margin);
+        let mut layout = self.root.layout(&mut self.state.text_renderer, bbox);
+        // Lists only know which rows to build once they've been laid out.
+        while self.root.update_rows(
+            &mut self.state.text_renderer,
+            &layout,
+            &mut vec![],
+            &mut [self.focus.focused_mut(), &mut self.captured],
+        ) {
+            layout = self.root.layout(&mut self.state.text_renderer, bbox);
+        }
+        self.root.draw(&mut self.state, &layout);
+        self.focus.draw(&mut self.state, &self.root, &layout);
         self.state.update();
+        self.layout = Some(layout);
+    }
+
+    fn dispatch_pointer(&mut self, event: PointerEvent) {
+        let Some(layout) = &self.layout else {
+            return;
+        };
+
+        let mut cx = EventContext {
+            measure: &mut self.state.text_renderer,
+        };
+
+        // Only a drag belongs to the captured element. The wheel still scrolls
+        // whatever is under the pointer.
+        let captured = match event {
+            PointerEvent::Scrolled { .. } => None,
+            _ => self.captured.as_ref(),
+        };
+
+        let mut new_focus = None;
+        if let Some(path) = captured {
+            if let (Some(elem), Some(node)) = (self.root.find_mut(path), layout.find(path)) {
+                elem.handle_pointer(node, &event, &mut cx);
+            }
+            if let PointerEvent::Released { .. } = event {
+                self.captured = None;
+            }
+        } else {
+            let handled_by = self.root.dispatch_pointer(layout, &event, &mut cx);
+            if let PointerEvent::Pressed { .. } = event {
+                // Clicking on something that takes keyboard input focuses it,
+                // clicking anywhere else drops the focus.
+                let focus = handled_by.clone().filter(|path| {
+                    self.root
+                        .find_mut(path)
+                        .is_some_and(|elem| elem.thing.focusable())
+                });
+                new_focus = Some(focus);
+                self.captured = handled_by;
+            }
+        }
+
+        // A release can end a drag somewhere other than where it started, so
+        // whatever is under the pointer now is hovered.
+        if let PointerEvent::Moved { pos } | PointerEvent::Released { pos, .. } = event {
+            self.root.update_hover(layout, pos, true);
+        }
+
+        if let Some(focus) = new_focus {
+            self.focus.set_focus(&mut self.root, focus);
+        }
+    }
+
+    fn dispatch_key(&mut self, event: KeyboardEvent) {
+        let before = self.focus.focused().map(<[usize]>::to_vec);
+        self.focus.handle_key(&mut self.root, &event);
+
+        // Keep whatever focus moved to on screen.
+        if let (Some(path), Some(layout)) = (self.focus.focused(), &self.layout) {
+            if before.as_deref() != Some(path) {
+                self.root.scroll_into_view(layout, path);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{font::FontRegistry, list::RowHeight};
+    use std::{cell::Cell, rc::Rc};
+    use winit::event::MouseButton;
+
+    /// Every char is 10 pixels wide and every line 20 pixels tall, so sizes in
+    /// tests are easy to work out by hand.
+    pub(crate) struct FixedMeasure;
+
+    impl TextMeasure for FixedMeasure {
+        fn string_width(&mut self, s: &str, _style: &TextStyle) -> f32 {
+            s.chars().count() as f32 * 10.0
+        }
+
+        fn line_height(&self, _style: &TextStyle) -> f32 {
+            20.0
+        }
+    }
+
+    pub(crate) fn text(text: &str) -> Thing {
+        Thing::Text {
+            text: text.to_string(),
+            style: TextStyle::default(),
+            text_color: [1.0; 4],
+            background_color: [0.0; 4],
+            align: (TextAlign::Start, TextAlign::Start),
+        }
+    }
+
+    pub(crate) fn wrapped(text: &str) -> Thing {
+        Thing::TextMultiLine {
+            text: vec![text.to_string()],
+            style: TextStyle::default(),
+            wrap_text: true,
+            text_color: [1.0; 4],
+            background_color: [0.0; 4],
+        }
+    }
+
+    fn quad() -> Thing {
+        Thing::Quad {
+            style: QuadStyle::new([0.0; 4]),
+        }
+    }
+
+    fn layout(thing: Thing, bbox: Bbox) -> LayoutNode {
+        Element::new(thing).layout(&mut FixedMeasure, bbox)
+    }
+
+    fn bboxes(node: &LayoutNode) -> Vec<Bbox> {
+        node.children.iter().map(|child| child.bbox).collect()
+    }
+
+    #[test]
+    fn hbox_sizes_fixed_percent_and_flex() {
+        let hbox = Hbox::new(vec![
+            Element::new(quad()).with_width(Size::Fixed(100.0)),
+            Element::new(quad()).with_width(Size::Percent(25.0)),
+            Element::new(quad()).with_width(Size::Flex(1.0)),
+            Element::new(quad()).with_width(Size::Flex(3.0)),
+        ]);
+        let node = layout(Thing::Hbox(hbox), Bbox::new(0.0, 0.0, 800.0, 100.0));
+
+        assert_eq!(
+            bboxes(&node),
+            vec![
+                Bbox::new(0.0, 0.0, 100.0, 100.0),
+                Bbox::new(100.0, 0.0, 300.0, 100.0),
+                Bbox::new(300.0, 0.0, 425.0, 100.0),
+                Bbox::new(425.0, 0.0, 800.0, 100.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn vbox_places_children_from_the_top() {
+        let vbox = Vbox::new(vec![
+            Element::new(text("hello")).with_height(Size::Fit),
+            Element::new(quad()),
+        ]);
+        let node = layout(Thing::Vbox(vbox), Bbox::new(0.0, 0.0, 200.0, 100.0));
+
+        assert_eq!(
+            bboxes(&node),
+            vec![
+                Bbox::new(0.0, 80.0, 200.0, 100.0),
+                Bbox::new(0.0, 0.0, 200.0, 80.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn fit_uses_the_size_of_the_text() {
+        let hbox = Hbox::new(vec![
+            Element::new(text("abc"))
+                .with_width(Size::Fit)
+                .with_height(Size::Fit),
+            Element::new(quad()),
+        ])
+        .with_align(Align::Start);
+        let node = layout(Thing::Hbox(hbox), Bbox::new(0.0, 0.0, 200.0, 100.0));
+
+        // Flex children fill the cross axis even when the box doesn't stretch.
+        assert_eq!(
+            bboxes(&node),
+            vec![
+                Bbox::new(0.0, 80.0, 30.0, 100.0),
+                Bbox::new(30.0, 0.0, 200.0, 100.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn margins_padding_and_gap() {
+        let hbox = Hbox::new(vec![
+            Element::new(quad())
+                .with_width(Size::Fixed(50.0))
+                .with_margin(Spacing::all(5.0)),
+            Element::new(quad()).with_margin(Spacing::new(0.0, 0.0, 0.0, 10.0)),
+        ])
+        .with_gap(20.0);
+        let node = Element::new(Thing::Hbox(hbox))
+            .with_padding(Spacing::symmetric(10.0, 20.0))
+            .layout(&mut FixedMeasure, Bbox::new(0.0, 0.0, 300.0, 100.0));
+
+        assert_eq!(node.content, Bbox::new(10.0, 20.0, 290.0, 80.0));
+        // 280 wide, less 10 of margin on the first child, 10 on the second and a
+        // gap of 20, leaves 190 for the flex child.
+        assert_eq!(
+            bboxes(&node),
+            vec![
+                Bbox::new(15.0, 25.0, 65.0, 75.0),
+                Bbox::new(100.0, 20.0, 290.0, 80.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn justify_spreads_the_free_space() {
+        let children = || {
+            vec![
+                Element::new(quad()).with_width(Size::Fixed(20.0)),
+                Element::new(quad()).with_width(Size::Fixed(20.0)),
+            ]
+        };
+        let bbox = Bbox::new(0.0, 0.0, 100.0, 10.0);
+        let starts = |justify| {
+            let hbox = Hbox::new(children()).with_justify(justify);
+            bboxes(&layout(Thing::Hbox(hbox), bbox))
+                .iter()
+                .map(|bbox| bbox.min.0)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(starts(Justify::Start), vec![0.0, 20.0]);
+        assert_eq!(starts(Justify::Center), vec![30.0, 50.0]);
+        assert_eq!(starts(Justify::End), vec![60.0, 80.0]);
+        assert_eq!(starts(Justify::SpaceBetween), vec![0.0, 80.0]);
+        assert_eq!(starts(Justify::SpaceAround), vec![15.0, 65.0]);
+    }
+
+    #[test]
+    fn align_places_children_on_the_cross_axis() {
+        let vbox = Vbox::new(vec![
+            Element::new(text("abcd")).with_width(Size::Fit),
+            Element::new(quad()).with_width(Size::Percent(50.0)),
+        ])
+        .with_align(Align::Center);
+        let node = layout(Thing::Vbox(vbox), Bbox::new(0.0, 0.0, 100.0, 100.0));
+
+        assert_eq!(
+            bboxes(&node),
+            vec![
+                Bbox::new(30.0, 50.0, 70.0, 100.0),
+                Bbox::new(25.0, 0.0, 75.0, 50.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn wrapped_text_fits_its_height_to_the_width() {
+        let vbox = Vbox::new(vec![
+            Element::new(wrapped("aaaa bbbb cccc"))
+                .with_height(Size::Fit)
+                .with_padding(Spacing::all(5.0)),
+            Element::new(quad()),
+        ]);
+        let node = layout(Thing::Vbox(vbox), Bbox::new(0.0, 0.0, 110.0, 200.0));
+
+        // 100 pixels of content fits "aaaa bbbb" but not the rest.
+        assert_eq!(node.children[0].bbox, Bbox::new(0.0, 150.0, 110.0, 200.0));
+    }
+
+    #[test]
+    fn fit_size_of_nested_boxes() {
+        let vbox = Vbox::new(vec![
+            Element::new(text("abc")).with_margin(Spacing::all(1.0)),
+            Element::new(Thing::Hbox(
+                Hbox::new(vec![Element::new(text("ab")), Element::new(text("abcdef"))])
+                    .with_gap(4.0),
+            )),
+        ])
+        .with_gap(2.0);
+
+        assert_eq!(
+            Element::new(Thing::Vbox(vbox))
+                .with_padding(Spacing::all(10.0))
+                .fit_size(&mut FixedMeasure, None),
+            (104.0, 64.0)
+        );
+    }
+
+    #[test]
+    fn fit_buttons_are_sized_to_their_label() {
+        let hbox = Hbox::new(vec![Element::new(Thing::Button(Button::new("OK", || {})))
+            .with_width(Size::Fit)
+            .with_height(Size::Fit)
+            .with_padding(Spacing::symmetric(12.0, 6.0))])
+        .with_align(Align::Start);
+        let node = layout(Thing::Hbox(hbox), Bbox::new(0.0, 0.0, 200.0, 100.0));
+
+        assert_eq!(bboxes(&node), vec![Bbox::new(0.0, 68.0, 44.0, 100.0)]);
+    }
+
+    #[test]
+    fn clip_of_a_descendant_covers_every_clipping_ancestor() {
+        let scroll = Scroll::new(Thing::Vbox(Vbox::new(vec![
+            Element::new(quad()).with_height(Size::Fixed(500.0))
+        ])));
+        let hbox = Hbox::new(vec![
+            Element::new(quad()).with_width(Size::Fixed(50.0)),
+            Element::new(Thing::Scroll(scroll)).with_padding(Spacing::all(10.0)),
+        ]);
+        let root = Element::new(Thing::Hbox(hbox)).with_clip(true);
+        let node = root.layout(&mut FixedMeasure, Bbox::new(0.0, 0.0, 200.0, 100.0));
+
+        assert_eq!(root.clip_of(&node, &[]), None);
+        assert_eq!(
+            root.clip_of(&node, &[0]),
+            Some(Bbox::new(0.0, 0.0, 200.0, 100.0))
+        );
+        assert_eq!(
+            root.clip_of(&node, &[1, 0, 0]),
+            Some(Bbox::new(60.0, 10.0, 190.0, 90.0))
+        );
+        assert_eq!(Element::new(quad()).clip_of(&node, &[0]), None);
+    }
+
+    /// A quad that handles every pointer event.
+    fn handles() -> Element {
+        Element::new(quad()).with_on_pointer(|_, _| EventStatus::Handled)
+    }
+
+    fn click(root: &mut Element, node: &LayoutNode, pos: (f32, f32)) -> Option<Vec<usize>> {
+        let event = PointerEvent::Pressed {
+            pos,
+            button: MouseButton::Left,
+        };
+        root.dispatch_pointer(
+            node,
+            &event,
+            &mut EventContext {
+                measure: &mut FixedMeasure,
+            },
+        )
+    }
+
+    #[test]
+    fn the_deepest_element_under_the_pointer_gets_the_event() {
+        let inner = Vbox::new(vec![handles(), handles()]);
+        let hbox = Hbox::new(vec![
+            Element::new(Thing::Vbox(inner)).with_on_pointer(|_, _| EventStatus::Handled),
+            handles(),
+        ]);
+        let mut root = Element::new(Thing::Hbox(hbox));
+        let node = root.layout(&mut FixedMeasure, Bbox::new(0.0, 0.0, 200.0, 100.0));
+
+        assert_eq!(click(&mut root, &node, (50.0, 25.0)), Some(vec![0, 1]));
+        assert_eq!(click(&mut root, &node, (150.0, 75.0)), Some(vec![1]));
+        assert_eq!(click(&mut root, &node, (250.0, 75.0)), None);
+    }
+
+    #[test]
+    fn later_siblings_are_on_top() {
+        let hbox = Hbox::new(vec![
+            handles().with_width(Size::Fixed(100.0)),
+            handles()
+                .with_width(Size::Fixed(100.0))
+                .with_margin(Spacing::new(0.0, 0.0, 0.0, -50.0)),
+        ]);
+        let mut root = Element::new(Thing::Hbox(hbox));
+        let node = root.layout(&mut FixedMeasure, Bbox::new(0.0, 0.0, 200.0, 100.0));
+
+        assert_eq!(click(&mut root, &node, (25.0, 50.0)), Some(vec![0]));
+        assert_eq!(click(&mut root, &node, (75.0, 50.0)), Some(vec![1]));
+    }
+
+    #[test]
+    fn ignored_events_bubble_to_the_parent() {
+        let seen = Rc::new(Cell::new(false));
+        let child_seen = seen.clone();
+        let child = Element::new(quad()).with_on_pointer(move |_, _| {
+            child_seen.set(true);
+            EventStatus::Ignored
+        });
+        let mut root = Element::new(Thing::Vbox(Vbox::new(vec![child])))
+            .with_on_pointer(|_, _| EventStatus::Handled);
+        let node = root.layout(&mut FixedMeasure, Bbox::new(0.0, 0.0, 100.0, 100.0));
+
+        assert_eq!(click(&mut root, &node, (50.0, 50.0)), Some(vec![]));
+        assert!(seen.get());
+    }
+
+    #[test]
+    fn scrolls_keep_their_scrollbar_and_padding_from_the_child() {
+        let mut scroll = Scroll::new(handles().with_height(Size::Fixed(500.0)));
+        scroll.scroll_to((0.0, 50.0));
+        let mut root = Element::new(Thing::Scroll(scroll)).with_padding(Spacing::all(10.0));
+        let node = root.layout(&mut FixedMeasure, Bbox::new(0.0, 0.0, 100.0, 100.0));
+
+        assert_eq!(click(&mut root, &node, (50.0, 50.0)), Some(vec![0]));
+        // The scrollbar is along the right edge of the content, over the child.
+        assert!(node.children[0].bbox.inside((88.0, 50.0)));
+        assert_eq!(click(&mut root, &node, (88.0, 50.0)), Some(vec![]));
+        // The child is scrolled up past the content, into the padding.
+        assert!(node.children[0].bbox.inside((50.0, 95.0)));
+        assert_eq!(click(&mut root, &node, (50.0, 95.0)), None);
+    }
+
+    #[test]
+    fn lists_keep_their_scrollbar_and_padding_from_the_rows() {
+        let list = List::new(100, RowHeight::Fixed(20.0), |_| handles());
+        let mut root = Element::new(Thing::List(list)).with_padding(Spacing::all(10.0));
+        let bbox = Bbox::new(0.0, 0.0, 100.0, 100.0);
+        let mut node = root.layout(&mut FixedMeasure, bbox);
+        while root.update_rows(&mut FixedMeasure, &node, &mut vec![], &mut []) {
+            node = root.layout(&mut FixedMeasure, bbox);
+        }
+
+        assert_eq!(click(&mut root, &node, (50.0, 85.0)), Some(vec![0]));
+        assert_eq!(click(&mut root, &node, (50.0, 65.0)), Some(vec![1]));
+        assert_eq!(click(&mut root, &node, (88.0, 85.0)), Some(vec![]));
+        assert_eq!(click(&mut root, &node, (50.0, 5.0)), None);
+    }
+
+    #[test]
+    fn measure_wraps_and_breaks_at_newlines() {
+        let style = TextStyle::default();
+        let (width, height, lines) = FixedMeasure.measure("ab cd\nefghij", &style, Some(40.0));
+
+        assert_eq!((width, height), (40.0, 80.0));
+        let ranges: Vec<_> = lines.iter().map(|line| line.range.clone()).collect();
+        assert_eq!(ranges, vec![0..2, 3..5, 6..10, 10..12]);
+    }
+
+    #[test]
+    fn font_registry_measures_without_a_gpu() {
+        let mut fonts = FontRegistry::new();
+        let style = TextStyle::default();
+
+        assert_eq!(fonts.string_width("", &style), 0.0);
+        let word = fonts.string_width("word", &style);
+        assert!(word > 0.0);
+        assert!(fonts.string_width("word word", &style) > 2.0 * word);
+        assert!(fonts.line_height(&style) >= style.size);
+
+        let (width, _, lines) = fonts.measure("word word word", &style, Some(word * 2.5));
+        assert_eq!(lines.len(), 2);
+        assert!(width <= word * 2.5);
     }
 }
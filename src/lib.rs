@@ -0,0 +1,39 @@
+mod app;
+mod buffer;
+mod button;
+mod event;
+mod focus;
+mod font;
+mod layout;
+mod list;
+mod quad;
+mod renderer;
+mod scroll;
+mod text_input;
+mod text_renderer;
+mod texture;
+mod textured_quad;
+mod wrap;
+
+pub use app::{run, App, WindowSettings};
+pub use button::{Button, ButtonStyle, ButtonStyles};
+pub use event::{EventContext, EventStatus, KeyboardEvent, PointerEvent, PointerHandler};
+pub use focus::FocusManager;
+pub use font::{
+    FontError, FontId, FontMetrics, FontRegistry, TextStyle, Weight, JETBRAINS_MONO, ROBOTO,
+};
+pub use layout::{
+    Align, Bbox, Element, Hbox, Justify, LayoutNode, Size, Spacing, TextAlign, TextMeasure, Thing,
+    Vbox,
+};
+pub use list::{List, RowHeight};
+pub use quad::{
+    Corners, Gradient, GradientKind, QuadRenderer, QuadStyle, Shadow, MAX_GRADIENT_STOPS,
+};
+pub use renderer::State;
+pub use scroll::{Scroll, ScrollbarStyle};
+pub use text_input::{TextInput, TextInputStyle};
+pub use text_renderer::TextRenderer;
+pub use textured_quad::TexturedQuadRenderer;
+pub use winit::event::MouseButton;
+pub use wrap::{wrap_lines, Line};
@@ -0,0 +1,619 @@
+use crate::{
+    event::{EventStatus, PointerEvent},
+    layout::{Bbox, Element, LayoutNode, TextMeasure},
+    renderer::State,
+    scroll::{ScrollbarStyle, Scrollbars},
+};
+use std::{fmt, iter, ops::Range};
+
+/// How tall the rows of a `List` are, in pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RowHeight {
+    /// Every row is the same height. The cheapest option, nothing is measured.
+    Fixed(f32),
+    /// Each row is measured the first time it's shown, and is assumed to be
+    /// `estimate` tall until then.
+    Measured { estimate: f32 },
+}
+
+/// The heights of the rows of a list with measured heights, kept in a Fenwick
+/// tree so the top of any row, and the row at any height, can be found
+/// without adding up every row above it.
+#[derive(Debug)]
+struct Heights {
+    estimate: f32,
+    /// The height of each row, if it's been measured.
+    measured: Vec<Option<f32>>,
+    /// `tree[k]` is the total height of the `k & -k` rows before row `k`.
+    /// Sums are kept in f64 so long lists don't drift.
+    tree: Vec<f64>,
+}
+
+impl Heights {
+    fn new(len: usize, estimate: f32) -> Self {
+        let mut heights = Self {
+            estimate,
+            measured: vec![None; len],
+            tree: vec![],
+        };
+        heights.rebuild();
+        heights
+    }
+
+    fn len(&self) -> usize {
+        self.measured.len()
+    }
+
+    fn get(&self, index: usize) -> f32 {
+        self.measured
+            .get(index)
+            .copied()
+            .flatten()
+            .unwrap_or(self.estimate)
+    }
+
+    fn is_measured(&self, index: usize) -> bool {
+        matches!(self.measured.get(index), Some(Some(_)))
+    }
+
+    fn set(&mut self, index: usize, height: f32) {
+        let delta = (height - self.get(index)) as f64;
+        self.measured[index] = Some(height);
+        let mut k = index + 1;
+        while k < self.tree.len() {
+            self.tree[k] += delta;
+            k += k & k.wrapping_neg();
+        }
+    }
+
+    /// Replace the rows in `range` with `count` rows that haven't been
+    /// measured.
+    fn splice(&mut self, range: Range<usize>, count: usize) {
+        self.measured.splice(range, iter::repeat_n(None, count));
+        self.rebuild();
+    }
+
+    /// Forget every measurement.
+    fn clear(&mut self) {
+        self.measured.fill(None);
+        self.rebuild();
+    }
+
+    fn rebuild(&mut self) {
+        let len = self.len();
+        self.tree = iter::once(0.0)
+            .chain((0..len).map(|i| self.get(i) as f64))
+            .collect();
+        for k in 1..=len {
+            let parent = k + (k & k.wrapping_neg());
+            if parent <= len {
+                self.tree[parent] += self.tree[k];
+            }
+        }
+    }
+
+    /// The total height of the rows before `index`.
+    fn sum(&self, index: usize) -> f32 {
+        let mut sum = 0.0;
+        let mut k = index.min(self.len());
+        while k > 0 {
+            sum += self.tree[k];
+            k -= k & k.wrapping_neg();
+        }
+        sum as f32
+    }
+
+    /// The row `y` pixels down from the top, or the number of rows if that's
+    /// past the end.
+    fn row_at(&self, y: f32) -> usize {
+        let len = self.len();
+        let mut row = 0;
+        let mut rest = y as f64;
+        let mut step = if len == 0 { 0 } else { 1 << len.ilog2() };
+        while step > 0 {
+            if row + step <= len && self.tree[row + step] <= rest {
+                row += step;
+                rest -= self.tree[row];
+            }
+            step >>= 1;
+        }
+        row
+    }
+}
+
+struct RowBuilder(Box<dyn FnMut(usize) -> Element>);
+
+impl fmt::Debug for RowBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RowBuilder")
+    }
+}
+
+/// A vertically scrolling list where only the rows in view exist. Rows are
+/// built from their index by `build_row` when they scroll into view and
+/// dropped when they scroll out, so a list can have any number of them.
+///
+/// Rows fill the width of the list. Their own height is ignored in favour of
+/// the list's `RowHeight`.
+#[derive(Debug)]
+pub struct List {
+    len: usize,
+    row_height: RowHeight,
+    /// Heights of the rows measured so far. Empty unless heights are measured.
+    heights: Heights,
+    build_row: RowBuilder,
+    /// Index of the first built row.
+    first: usize,
+    /// The built rows, in order.
+    elements: Vec<Element>,
+    pub style: ScrollbarStyle,
+    scrollbars: Scrollbars,
+}
+
+impl List {
+    pub fn new(
+        len: usize,
+        row_height: RowHeight,
+        build_row: impl FnMut(usize) -> Element + 'static,
+    ) -> Self {
+        let heights = match row_height {
+            RowHeight::Fixed(height) => Heights::new(0, height),
+            RowHeight::Measured { estimate } => Heights::new(len, estimate),
+        };
+        Self {
+            len,
+            row_height,
+            heights,
+            build_row: RowBuilder(Box::new(build_row)),
+            first: 0,
+            elements: vec![],
+            style: ScrollbarStyle::default(),
+            scrollbars: Scrollbars::default(),
+        }
+    }
+
+    pub fn with_style(mut self, style: ScrollbarStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The row at `index`, if it's currently built.
+    pub fn row(&self, index: usize) -> Option<&Element> {
+        self.elements.get(index.checked_sub(self.first)?)
+    }
+
+    pub fn row_mut(&mut self, index: usize) -> Option<&mut Element> {
+        self.elements.get_mut(index.checked_sub(self.first)?)
+    }
+
+    /// Add `count` rows before the row at `index`. Rows added above the view
+    /// push the scroll offset down with them, so what's on screen stays put.
+    pub fn insert_rows(&mut self, index: usize, count: usize) {
+        let index = index.min(self.len);
+        let above = self.row_top(index) < self.scrollbars.offset.1;
+
+        if let RowHeight::Measured { .. } = self.row_height {
+            self.heights.splice(index..index, count);
+        }
+        self.len += count;
+
+        if above {
+            self.scrollbars.offset.1 += self.rows_height(index..index + count);
+        }
+
+        // Built rows keep their index, any after the new ones are rebuilt.
+        if index <= self.first {
+            self.first += count;
+        } else if index < self.first + self.elements.len() {
+            self.elements.truncate(index - self.first);
+        }
+    }
+
+    /// Remove the rows in `range`. Like with `insert_rows`, what's on screen
+    /// stays put unless it was removed.
+    pub fn remove_rows(&mut self, range: Range<usize>) {
+        let range = range.start.min(self.len)..range.end.min(self.len);
+        if range.is_empty() {
+            return;
+        }
+
+        let top = self.row_top(range.start);
+        let bottom = top + self.rows_height(range.clone());
+        let offset = self.scrollbars.offset.1;
+        if bottom <= offset {
+            self.scrollbars.offset.1 -= bottom - top;
+        } else if top < offset {
+            self.scrollbars.offset.1 = top;
+        }
+
+        if let RowHeight::Measured { .. } = self.row_height {
+            self.heights.splice(range.clone(), 0);
+        }
+        self.len -= range.len();
+
+        if range.end <= self.first {
+            self.first -= range.len();
+        } else if range.start <= self.first {
+            self.first = range.start;
+            self.elements.clear();
+        } else if range.start < self.first + self.elements.len() {
+            self.elements.truncate(range.start - self.first);
+        }
+    }
+
+    /// Drop every built row so they're built again, for when the data behind
+    /// them has changed. Measured heights are measured again too.
+    pub fn refresh(&mut self) {
+        self.elements.clear();
+        self.heights.clear();
+    }
+
+    /// How far the list is scrolled down, in pixels.
+    pub fn offset(&self) -> f32 {
+        self.scrollbars.offset.1
+    }
+
+    /// Scroll down to `offset` pixels. Offsets past the end of the list are
+    /// pulled back in when it's next laid out.
+    pub fn scroll_to(&mut self, offset: f32) {
+        self.scrollbars.offset.1 = offset;
+    }
+
+    /// Scroll so the row at `index` is at the top of the view.
+    pub fn scroll_to_row(&mut self, index: usize) {
+        self.scrollbars.offset.1 = self.row_top(index.min(self.len));
+    }
+
+    /// Scroll as little as possible to make `target` visible, using the list's
+    /// own layout. Returns how far the rows moved, like
+    /// `Scroll::scroll_into_view`.
+    pub fn scroll_into_view(&mut self, node: &LayoutNode, target: &Bbox) -> (f32, f32) {
+        let viewport = node.content;
+        let max = self.max_offset(&viewport);
+        let current = self.scrollbars.clamped(max);
+        let mut offset = current;
+        if target.max.1 > viewport.max.1 {
+            offset.1 -= target.max.1 - viewport.max.1;
+        } else if target.min.1 < viewport.min.1 {
+            offset.1 += (viewport.min.1 - target.min.1).min(viewport.max.1 - target.max.1);
+        }
+
+        self.scrollbars.set(offset, max);
+        (0.0, self.scrollbars.offset.1 - current.1)
+    }
+
+    pub(crate) fn elements(&self) -> &Vec<Element> {
+        &self.elements
+    }
+
+    pub(crate) fn elements_mut(&mut self) -> &mut Vec<Element> {
+        &mut self.elements
+    }
+
+    /// Build the rows that are in view and drop the rest, keeping the ones
+    /// that were already built. New rows are measured if they need to be,
+    /// which can bring more rows into view, so this goes until nothing
+    /// changes.
+    ///
+    /// Returns whether any rows changed, in which case `node` is out of date.
+    /// Paths in `tracked` that lead into a row through the list at `path`
+    /// follow the row to its new child index, or are cleared if it was
+    /// dropped.
+    pub(crate) fn update_rows(
+        &mut self,
+        measure: &mut dyn TextMeasure,
+        node: &LayoutNode,
+        path: &[usize],
+        tracked: &mut [&mut Option<Vec<usize>>],
+    ) -> bool {
+        let old_first = self.first;
+        // The rows that were built before and still are.
+        let mut kept = self.first..self.first + self.elements.len();
+        let mut changed = false;
+        loop {
+            let needed = self.visible_rows(&node.content);
+            let built = self.first..self.first + self.elements.len();
+            if needed == built {
+                break;
+            }
+            changed = true;
+            kept = kept.start.max(needed.start)..kept.end.min(needed.end);
+
+            let mut old: Vec<Option<Element>> = self.elements.drain(..).map(Some).collect();
+            for i in needed.clone() {
+                let row = match old.get_mut(i.wrapping_sub(built.start)) {
+                    Some(row) if built.contains(&i) => row.take(),
+                    _ => None,
+                };
+                let row = row.unwrap_or_else(|| (self.build_row.0)(i));
+
+                if i < self.heights.len() && !self.heights.is_measured(i) {
+                    let width = (node.content.width() - row.margin.horizontal()).max(0.0);
+                    let height = row.fit_size(measure, Some(width)).1 + row.margin.vertical();
+                    self.heights.set(i, height);
+                }
+                self.elements.push(row);
+            }
+            self.first = needed.start;
+        }
+
+        if changed {
+            for tracked in tracked.iter_mut() {
+                let Some(row) = tracked
+                    .as_ref()
+                    .and_then(|tracked| tracked.strip_prefix(path)?.first())
+                    .map(|child| old_first + child)
+                else {
+                    continue;
+                };
+                if kept.contains(&row) {
+                    if let Some(tracked) = tracked.as_mut() {
+                        tracked[path.len()] = row - self.first;
+                    }
+                } else {
+                    **tracked = None;
+                }
+            }
+        }
+        changed
+    }
+
+    /// Lay the built rows out one after the other, moved by the scroll offset.
+    pub(crate) fn layout(&self, measure: &mut dyn TextMeasure, viewport: Bbox) -> Vec<LayoutNode> {
+        let offset = self.scrollbars.clamped(self.max_offset(&viewport)).1;
+        let mut top = viewport.max.1 + offset - self.row_top(self.first);
+
+        let mut children = Vec::with_capacity(self.elements.len());
+        for (i, row) in (self.first..).zip(&self.elements) {
+            let height = self.height(i);
+            let margin = row.margin;
+            let x0 = viewport.min.0 + margin.left;
+            let y1 = top - margin.top;
+            let bbox = Bbox::new(
+                x0,
+                (top - height + margin.bottom).min(y1),
+                (viewport.max.0 - margin.right).max(x0),
+                y1,
+            );
+            children.push(row.layout(measure, bbox));
+            top -= height;
+        }
+        children
+    }
+
+    pub(crate) fn draw(&self, state: &mut State, node: &LayoutNode) {
+        state.push_clip(&node.content);
+        for (row, child) in self.elements.iter().zip(&node.children) {
+            row.draw(state, child);
+        }
+        state.pop_clip();
+
+        let max = self.max_offset(&node.content);
+        self.scrollbars.draw(state, &self.style, &node.content, max);
+    }
+
+    pub(crate) fn on_scrollbar(&self, node: &LayoutNode, pos: (f32, f32)) -> bool {
+        let max = self.max_offset(&node.content);
+        self.scrollbars
+            .on_scrollbar(&self.style, &node.content, max, pos)
+    }
+
+    pub(crate) fn handle_pointer(
+        &mut self,
+        node: &LayoutNode,
+        event: &PointerEvent,
+    ) -> EventStatus {
+        let max = self.max_offset(&node.content);
+        self.scrollbars
+            .handle_pointer(&self.style, &node.content, max, event)
+    }
+
+    fn height(&self, index: usize) -> f32 {
+        match self.row_height {
+            RowHeight::Fixed(height) => height,
+            RowHeight::Measured { .. } => self.heights.get(index),
+        }
+    }
+
+    fn rows_height(&self, rows: Range<usize>) -> f32 {
+        match self.row_height {
+            RowHeight::Fixed(height) => height * rows.len() as f32,
+            RowHeight::Measured { .. } => self.heights.sum(rows.end) - self.heights.sum(rows.start),
+        }
+    }
+
+    /// How far the top of the row at `index` is from the top of the list.
+    fn row_top(&self, index: usize) -> f32 {
+        match self.row_height {
+            RowHeight::Fixed(height) => height * index as f32,
+            RowHeight::Measured { .. } => self.heights.sum(index),
+        }
+    }
+
+    /// The index of the row `y` pixels down from the top of the list, or the
+    /// number of rows if that's past the end.
+    fn row_at(&self, y: f32) -> usize {
+        match self.row_height {
+            RowHeight::Fixed(height) if height > 0.0 => {
+                ((y / height).max(0.0) as usize).min(self.len)
+            }
+            RowHeight::Fixed(_) => self.len,
+            RowHeight::Measured { .. } => self.heights.row_at(y),
+        }
+    }
+
+    /// The rows that are at least partly inside of the viewport.
+    fn visible_rows(&self, viewport: &Bbox) -> Range<usize> {
+        let offset = self.scrollbars.clamped(self.max_offset(viewport)).1;
+        let first = self.row_at(offset);
+        let last = self.row_at(offset + viewport.height());
+        first..(last + 1).min(self.len)
+    }
+
+    fn max_offset(&self, viewport: &Bbox) -> (f32, f32) {
+        let total = self.row_top(self.len);
+        (0.0, (total - viewport.height()).max(0.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::{
+        tests::{wrapped, FixedMeasure},
+        Size, Thing,
+    };
+
+    /// Lay `list` out in `viewport` until its rows stop changing, like
+    /// `SceneRoot::update` does.
+    fn settle(list: &mut Element, viewport: Bbox) -> LayoutNode {
+        let mut node = list.layout(&mut FixedMeasure, viewport);
+        while list.update_rows(&mut FixedMeasure, &node, &mut vec![], &mut []) {
+            node = list.layout(&mut FixedMeasure, viewport);
+        }
+        node
+    }
+
+    fn list(thing: &mut Element) -> &mut List {
+        match &mut thing.thing {
+            Thing::List(list) => list,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Rows are one line tall, except every third which is two lines in a
+    /// 100 pixel wide list.
+    fn measured(len: usize) -> Element {
+        let row = |i: usize| {
+            let text = if i.is_multiple_of(3) {
+                "two lines of text"
+            } else {
+                "one"
+            };
+            Element::new(wrapped(text)).with_height(Size::Fit)
+        };
+        Element::new(Thing::List(List::new(
+            len,
+            RowHeight::Measured { estimate: 30.0 },
+            row,
+        )))
+    }
+
+    fn viewport() -> Bbox {
+        Bbox::new(0.0, 0.0, 100.0, 100.0)
+    }
+
+    #[test]
+    fn heights_agree_with_adding_them_up() {
+        let mut heights = Heights::new(37, 10.0);
+        for i in (0..37).step_by(3) {
+            heights.set(i, i as f32);
+        }
+        heights.splice(5..9, 2);
+        heights.set(6, 4.0);
+
+        let mut top = 0.0;
+        for i in 0..heights.len() {
+            assert_eq!(heights.sum(i), top);
+            // Rows with no height are never found.
+            if heights.get(i) > 0.0 {
+                assert_eq!(heights.row_at(top), i);
+                assert_eq!(heights.row_at(top + heights.get(i) - 0.5), i);
+            }
+            top += heights.get(i);
+        }
+        assert_eq!(heights.sum(heights.len()), top);
+        assert_eq!(heights.row_at(top), heights.len());
+        assert_eq!(heights.row_at(-5.0), 0);
+    }
+
+    #[test]
+    fn only_visible_rows_are_built() {
+        let mut elem = Element::new(Thing::List(List::new(
+            100_000,
+            RowHeight::Fixed(20.0),
+            |i| Element::new(wrapped(&i.to_string())),
+        )));
+        list(&mut elem).scroll_to(1_000_010.0);
+        let node = settle(&mut elem, viewport());
+
+        let list = list(&mut elem);
+        assert_eq!(list.elements().len(), 6);
+        assert!(list.row(50_000).is_some());
+        assert!(list.row(50_006).is_none());
+        assert_eq!(node.children[0].bbox, Bbox::new(0.0, 90.0, 100.0, 110.0));
+    }
+
+    #[test]
+    fn measured_rows_are_placed_by_their_height() {
+        let mut elem = measured(1000);
+        let node = settle(&mut elem, viewport());
+
+        let bottoms: Vec<f32> = node.children.iter().map(|child| child.bbox.min.1).collect();
+        assert_eq!(bottoms, vec![60.0, 40.0, 20.0, -20.0]);
+        assert_eq!(list(&mut elem).row_top(4), 120.0);
+    }
+
+    #[test]
+    fn rows_inserted_above_the_view_keep_it_still() {
+        let mut elem = measured(1000);
+        list(&mut elem).scroll_to(200.0);
+        settle(&mut elem, viewport());
+        let first = list(&mut elem).first;
+
+        list(&mut elem).insert_rows(0, 5);
+        assert_eq!(list(&mut elem).offset(), 200.0 + 5.0 * 30.0);
+        assert_eq!(list(&mut elem).first, first + 5);
+
+        list(&mut elem).remove_rows(0..5);
+        assert_eq!(list(&mut elem).offset(), 200.0);
+        assert_eq!(list(&mut elem).first, first);
+    }
+
+    #[test]
+    fn tracked_paths_follow_their_row() {
+        let mut elem = Element::new(Thing::List(List::new(100, RowHeight::Fixed(20.0), |i| {
+            Element::new(wrapped(&i.to_string()))
+        })));
+        settle(&mut elem, viewport());
+
+        let mut focused = Some(vec![2, 0]);
+        let mut captured = Some(vec![0]);
+        let mut list_itself = Some(vec![]);
+        let rebuild = |elem: &mut Element, offset, tracked: &mut [&mut Option<Vec<usize>>]| {
+            list(elem).scroll_to(offset);
+            let node = elem.layout(&mut FixedMeasure, viewport());
+            elem.update_rows(&mut FixedMeasure, &node, &mut vec![], tracked);
+        };
+
+        // Row 2 is now the second child, and row 0 is gone.
+        rebuild(
+            &mut elem,
+            20.0,
+            &mut [&mut focused, &mut captured, &mut list_itself],
+        );
+        assert_eq!(focused, Some(vec![1, 0]));
+        assert_eq!(captured, None);
+        assert_eq!(list_itself, Some(vec![]));
+
+        rebuild(&mut elem, 1000.0, &mut [&mut focused]);
+        assert_eq!(focused, None);
+    }
+
+    #[test]
+    fn scroll_to_row_uses_measured_heights() {
+        let mut elem = measured(1000);
+        settle(&mut elem, viewport());
+        list(&mut elem).scroll_to_row(4);
+
+        assert_eq!(list(&mut elem).offset(), 120.0);
+    }
+}
@@ -1,10 +1,11 @@
+use std::{ops::Range, sync::Arc};
 use wgpu::{
-    util::DeviceExt, BindGroup, Buffer, BufferDescriptor, Device, Queue, RenderPass,
-    RenderPipeline, TextureFormat,
+    util::DeviceExt, BindGroup, Buffer, Device, Queue, RenderPass, RenderPipeline, TextureFormat,
 };
 use winit::dpi::PhysicalSize;
 
-use crate::layout::Bbox;
+use crate::buffer::GrowableBuffer;
+use crate::layout::{Bbox, Spacing};
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
@@ -33,49 +34,275 @@ pub struct Quad {
     pub size: [f32; 2],
     pub color: [f32; 4],
     pub border_color: [f32; 4],
-    pub border: f32,
-    pub radius: f32,
+    /// top, right, bottom, left
+    pub border: [f32; 4],
+    /// top left, top right, bottom right, bottom left
+    pub radius: [f32; 4],
+    /// kind (0 solid, 1 linear, 2 radial, 3 shadow), then the angle for
+    /// linear gradients, the radius and center for radial ones, or the blur
+    /// radius for shadows
+    pub gradient: [f32; 4],
+    pub stop_offsets: [f32; MAX_GRADIENT_STOPS],
+    pub stop_colors: [[f32; 4]; MAX_GRADIENT_STOPS],
 }
 
 impl Quad {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 12] = wgpu::vertex_attr_array![
+        1 => Float32x2,
+        2 => Float32x2,
+        3 => Float32x4,
+        4 => Float32x4,
+        5 => Float32x4,
+        6 => Float32x4,
+        7 => Float32x4,
+        8 => Float32x4,
+        9 => Float32x4,
+        10 => Float32x4,
+        11 => Float32x4,
+        12 => Float32x4,
+    ];
+
     fn desc() -> wgpu::VertexBufferLayout<'static> {
         wgpu::VertexBufferLayout {
             array_stride: std::mem::size_of::<Quad>() as wgpu::BufferAddress,
             step_mode: wgpu::VertexStepMode::Instance,
-            attributes: &[
-                wgpu::VertexAttribute {
-                    offset: 0,
-                    shader_location: 1,
-                    format: wgpu::VertexFormat::Float32x2,
-                },
-                wgpu::VertexAttribute {
-                    offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
-                    shader_location: 2,
-                    format: wgpu::VertexFormat::Float32x2,
-                },
-                wgpu::VertexAttribute {
-                    offset: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
-                    shader_location: 3,
-                    format: wgpu::VertexFormat::Float32x4,
-                },
-                wgpu::VertexAttribute {
-                    offset: std::mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
-                    shader_location: 4,
-                    format: wgpu::VertexFormat::Float32x4,
-                },
-                wgpu::VertexAttribute {
-                    offset: std::mem::size_of::<[f32; 12]>() as wgpu::BufferAddress,
-                    shader_location: 5,
-                    format: wgpu::VertexFormat::Float32,
-                },
-                wgpu::VertexAttribute {
-                    offset: std::mem::size_of::<[f32; 13]>() as wgpu::BufferAddress,
-                    shader_location: 6,
-                    format: wgpu::VertexFormat::Float32,
-                },
-            ],
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+/// A value for each corner of a quad, in pixels.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Corners {
+    pub top_left: f32,
+    pub top_right: f32,
+    pub bottom_right: f32,
+    pub bottom_left: f32,
+}
+
+impl Corners {
+    pub fn new(top_left: f32, top_right: f32, bottom_right: f32, bottom_left: f32) -> Self {
+        Self {
+            top_left,
+            top_right,
+            bottom_right,
+            bottom_left,
+        }
+    }
+
+    /// The same value for every corner.
+    pub fn all(amount: f32) -> Self {
+        Self::new(amount, amount, amount, amount)
+    }
+
+    /// Only round the top two corners, like a tab.
+    pub fn top(amount: f32) -> Self {
+        Self::new(amount, amount, 0.0, 0.0)
+    }
+
+    /// Only round the bottom two corners, like a dropdown attached below
+    /// something.
+    pub fn bottom(amount: f32) -> Self {
+        Self::new(0.0, 0.0, amount, amount)
+    }
+
+    fn to_array(self) -> [f32; 4] {
+        [
+            self.top_left,
+            self.top_right,
+            self.bottom_right,
+            self.bottom_left,
+        ]
+    }
+}
+
+/// The most color stops a single gradient can have.
+pub const MAX_GRADIENT_STOPS: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GradientKind {
+    /// Colors change along a line through the center of the quad. The angle is
+    /// in degrees clockwise from pointing up, so 90 goes from left to right.
+    /// Like in CSS, the line is long enough that the first and last stops land
+    /// exactly on the corners.
+    Linear { angle: f32 },
+    /// Colors change in circles around `center`, which is a fraction of the
+    /// quad's size measured from its top left corner. `radius` is in pixels, a
+    /// radius of zero reaches the farthest corner.
+    Radial { center: [f32; 2], radius: f32 },
+}
+
+/// A fill that blends between up to `MAX_GRADIENT_STOPS` colors. Each stop is
+/// an offset from 0 to 1 along the gradient and the color at that offset.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Gradient {
+    pub kind: GradientKind,
+    stops: [(f32, [f32; 4]); MAX_GRADIENT_STOPS],
+    len: usize,
+}
+
+impl Gradient {
+    /// Offsets are clamped to between 0 and 1 and the stops sorted by them.
+    /// Past `MAX_GRADIENT_STOPS` only the first stops and the last one are
+    /// kept, so the gradient still ends on the right color. Without any stops
+    /// the gradient is transparent.
+    pub fn new(kind: GradientKind, stops: &[(f32, [f32; 4])]) -> Self {
+        let mut sorted: Vec<(f32, [f32; 4])> = stops
+            .iter()
+            .map(|&(offset, color)| (offset.clamp(0.0, 1.0), color))
+            .collect();
+        sorted.sort_by(|a, b| a.0.total_cmp(&b.0));
+        if sorted.len() > MAX_GRADIENT_STOPS {
+            sorted.drain(MAX_GRADIENT_STOPS - 1..sorted.len() - 1);
+        }
+        let last = sorted.last().copied().unwrap_or((0.0, [0.0; 4]));
+
+        let mut padded = [last; MAX_GRADIENT_STOPS];
+        padded[..sorted.len()].copy_from_slice(&sorted);
+        Self {
+            kind,
+            stops: padded,
+            len: sorted.len().max(1),
+        }
+    }
+
+    pub fn linear(angle: f32, stops: &[(f32, [f32; 4])]) -> Self {
+        Self::new(GradientKind::Linear { angle }, stops)
+    }
+
+    /// A circular gradient from the center of the quad out to its corners.
+    pub fn radial(stops: &[(f32, [f32; 4])]) -> Self {
+        Self::new(
+            GradientKind::Radial {
+                center: [0.5, 0.5],
+                radius: 0.0,
+            },
+            stops,
+        )
+    }
+
+    pub fn stops(&self) -> &[(f32, [f32; 4])] {
+        &self.stops[..self.len]
+    }
+
+    /// The gradient's parameters as the quad shader expects them. Unused stops
+    /// repeat the last one so the shader doesn't need to know how many there
+    /// are.
+    fn to_instance(self) -> ([f32; 4], [f32; 4], [[f32; 4]; 4]) {
+        let params = match self.kind {
+            GradientKind::Linear { angle } => [1.0, angle.to_radians(), 0.0, 0.0],
+            GradientKind::Radial { center, radius } => [2.0, radius, center[0], center[1]],
+        };
+        (
+            params,
+            self.stops.map(|(offset, _)| offset),
+            self.stops.map(|(_, color)| color),
+        )
+    }
+}
+
+/// A soft shadow drawn beneath a quad, like CSS's `box-shadow`. Sizes are in
+/// pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Shadow {
+    /// How far the shadow is moved from the quad. Positive x moves it right
+    /// and positive y moves it down.
+    pub offset: [f32; 2],
+    /// How far the edge of the shadow fades out. Zero gives a hard edge.
+    pub blur: f32,
+    /// Grows the shadow on every side before blurring it, or shrinks it when
+    /// negative.
+    pub spread: f32,
+    pub color: [f32; 4],
+}
+
+impl Shadow {
+    pub fn new(offset: [f32; 2], blur: f32, color: [f32; 4]) -> Self {
+        Self {
+            offset,
+            blur,
+            spread: 0.0,
+            color,
         }
     }
+
+    pub fn with_spread(mut self, spread: f32) -> Self {
+        self.spread = spread;
+        self
+    }
+}
+
+/// How a quad is filled and outlined. Sizes are in pixels.
+///
+/// Gradients and shadows are kept behind an `Arc`, since most quads have
+/// neither, styles are kept for every state of every widget, and they're
+/// cloned every time they're drawn.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuadStyle {
+    pub color: [f32; 4],
+    /// Radius of each rounded corner. Clamped to half of the quad's shortest
+    /// side.
+    pub radius: Corners,
+    /// Width of the border on each side, drawn inside of the quad's edges.
+    pub border: Spacing,
+    pub border_color: [f32; 4],
+    /// Fills the quad instead of `color` when set.
+    pub gradient: Option<Arc<Gradient>>,
+    pub shadow: Option<Arc<Shadow>>,
+}
+
+impl QuadStyle {
+    pub fn new(color: [f32; 4]) -> Self {
+        Self {
+            color,
+            radius: Corners::default(),
+            border: Spacing::default(),
+            border_color: [0.0, 0.0, 0.0, 1.0],
+            gradient: None,
+            shadow: None,
+        }
+    }
+
+    /// Round every corner by `radius`.
+    pub fn with_radius(mut self, radius: f32) -> Self {
+        self.radius = Corners::all(radius);
+        self
+    }
+
+    pub fn with_corners(mut self, radius: Corners) -> Self {
+        self.radius = radius;
+        self
+    }
+
+    /// A border `width` pixels wide on every side.
+    pub fn with_border(self, width: f32, border_color: [f32; 4]) -> Self {
+        self.with_border_sides(Spacing::all(width), border_color)
+    }
+
+    /// A border with a different width on each side. Sides with a width of
+    /// zero have no border, so `Spacing::new(0.0, 0.0, 1.0, 0.0)` is a divider
+    /// along the bottom.
+    pub fn with_border_sides(mut self, border: Spacing, border_color: [f32; 4]) -> Self {
+        self.border = border;
+        self.border_color = border_color;
+        self
+    }
+
+    pub fn with_gradient(mut self, gradient: Gradient) -> Self {
+        self.gradient = Some(Arc::new(gradient));
+        self
+    }
+
+    pub fn with_shadow(mut self, shadow: Shadow) -> Self {
+        self.shadow = Some(Arc::new(shadow));
+        self
+    }
+}
+
+impl From<[f32; 4]> for QuadStyle {
+    fn from(color: [f32; 4]) -> Self {
+        Self::new(color)
+    }
 }
 
 /*
@@ -115,7 +342,7 @@ pub struct QuadRenderer {
     num_indices: u32,
 
     instances: Vec<Quad>,
-    instance_buffer: Buffer,
+    instance_buffer: GrowableBuffer,
 
     uniforms: Uniforms,
     uniforms_buffer: Buffer,
@@ -222,14 +449,14 @@ impl QuadRenderer {
         });
         let num_indices = INDICES.len() as u32;
 
-        let max_instances = 1024;
+        let initial_instances = 1024;
         let instances = vec![];
-        let instance_buffer = device.create_buffer(&BufferDescriptor {
-            label: Some("Instance Buffer"),
-            size: max_instances * std::mem::size_of::<Quad>() as u64,
-            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
+        let instance_buffer = GrowableBuffer::new(
+            device,
+            "Instance Buffer",
+            wgpu::BufferUsages::VERTEX,
+            initial_instances * std::mem::size_of::<Quad>() as u64,
+        );
 
         QuadRenderer {
             render_pipeline,
@@ -250,42 +477,145 @@ impl QuadRenderer {
         self.instances.clear();
     }
 
-    pub fn update(&mut self, size: PhysicalSize<u32>, queue: &Queue) {
+    pub fn update(&mut self, size: PhysicalSize<u32>, device: &Device, queue: &Queue) {
         self.uniforms = Uniforms::new(size);
         queue.write_buffer(
             &self.uniforms_buffer,
             0,
             bytemuck::cast_slice(&[self.uniforms]),
         );
-        queue.write_buffer(
-            &self.instance_buffer,
-            0,
-            bytemuck::cast_slice(&self.instances),
-        );
+        self.instance_buffer
+            .write(device, queue, bytemuck::cast_slice(&self.instances));
     }
 
-    pub fn add_instance(&mut self, color: [f32; 4], bbox: &Bbox) {
+    /// Add a quad filling the bbox. Takes either a `QuadStyle` or a plain
+    /// color. The quad's shadow, if it has one, is added first so it ends up
+    /// underneath.
+    pub fn add_instance(&mut self, style: impl Into<QuadStyle>, bbox: &Bbox) {
+        let style = style.into();
+        if let Some(shadow) = &style.shadow {
+            self.add_shadow(shadow, style.radius, bbox);
+        }
+
         let sizex = bbox.width() / self.uniforms.window_size[0];
         let sizey = bbox.height() / self.uniforms.window_size[1];
 
         let origin = bbox.center();
+        let (gradient, stop_offsets, stop_colors) = match &style.gradient {
+            Some(gradient) => gradient.to_instance(),
+            None => ([0.0; 4], [0.0; 4], [[0.0; 4]; 4]),
+        };
 
         self.instances.push(Quad {
             origin: [origin.0, origin.1],
             size: [sizex, sizey],
-            color,
-            radius: 0.0,
-            border: 0.0,
-            border_color: [0.0, 0.0, 0.0, 1.0],
+            color: style.color,
+            radius: style.radius.to_array(),
+            border: [
+                style.border.top,
+                style.border.right,
+                style.border.bottom,
+                style.border.left,
+            ],
+            border_color: style.border_color,
+            gradient,
+            stop_offsets,
+            stop_colors,
+        });
+    }
+
+    /// The shadow is the quad's shape, moved and grown by the spread, then
+    /// blurred in the shader. The instance is made bigger by the blur radius
+    /// so there's room for the blurred edge; the shader takes it off again to
+    /// find the shape.
+    fn add_shadow(&mut self, shadow: &Shadow, radius: Corners, bbox: &Bbox) {
+        let shape = bbox
+            .translate(shadow.offset[0], -shadow.offset[1])
+            .expand(shadow.spread);
+        if shape.width() <= 0.0 || shape.height() <= 0.0 {
+            return;
+        }
+
+        // Rounded corners grow with the spread, square ones stay square.
+        let radius = radius.to_array().map(|r| {
+            if r > 0.0 {
+                (r + shadow.spread).max(0.0)
+            } else {
+                0.0
+            }
+        });
+        let blur = shadow.blur.max(0.0);
+        let drawn = shape.expand(blur);
+        let origin = drawn.center();
+
+        self.instances.push(Quad {
+            origin: [origin.0, origin.1],
+            size: [
+                drawn.width() / self.uniforms.window_size[0],
+                drawn.height() / self.uniforms.window_size[1],
+            ],
+            color: shadow.color,
+            border_color: [0.0; 4],
+            border: [0.0; 4],
+            radius,
+            gradient: [3.0, blur, 0.0, 0.0],
+            stop_offsets: [0.0; 4],
+            stop_colors: [[0.0; 4]; 4],
         });
     }
 
-    pub fn render<'rpass>(&'rpass self, rpass: &mut RenderPass<'rpass>) {
+    /// Add an unfilled rectangle with edges `width` pixels thick, drawn just
+    /// inside of the bbox.
+    pub fn add_outline(&mut self, color: [f32; 4], bbox: &Bbox, width: f32) {
+        self.add_instance(
+            QuadStyle::new([0.0, 0.0, 0.0, 0.0]).with_border(width, color),
+            bbox,
+        );
+    }
+
+    /// How many quads have been added since the last `clear`.
+    pub fn instance_count(&self) -> u32 {
+        self.instances.len() as u32
+    }
+
+    /// Draw a range of the quads added so far, in the order they were added.
+    pub fn render<'rpass>(&'rpass self, rpass: &mut RenderPass<'rpass>, instances: Range<u32>) {
         rpass.set_pipeline(&self.render_pipeline);
         rpass.set_bind_group(0, &self.uniforms_bind_group, &[]);
         rpass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-        rpass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+        rpass.set_vertex_buffer(1, self.instance_buffer.slice());
         rpass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-        rpass.draw_indexed(0..self.num_indices, 0, 0..self.instances.len() as u32);
+        rpass.draw_indexed(0..self.num_indices, 0, instances);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+    const GREEN: [f32; 4] = [0.0, 1.0, 0.0, 1.0];
+    const BLUE: [f32; 4] = [0.0, 0.0, 1.0, 1.0];
+
+    #[test]
+    fn gradient_stops_are_clamped_and_sorted() {
+        let gradient = Gradient::linear(0.0, &[(1.5, BLUE), (0.5, GREEN), (-1.0, RED)]);
+
+        assert_eq!(gradient.stops(), &[(0.0, RED), (0.5, GREEN), (1.0, BLUE)]);
+    }
+
+    #[test]
+    fn extra_gradient_stops_keep_the_last_one() {
+        let stops: Vec<_> = (0..6).map(|i| (i as f32 / 5.0, [i as f32; 4])).collect();
+        let gradient = Gradient::radial(&stops);
+
+        assert_eq!(gradient.stops().len(), MAX_GRADIENT_STOPS);
+        assert_eq!(&gradient.stops()[..3], &stops[..3]);
+        assert_eq!(gradient.stops()[3], stops[5]);
+    }
+
+    #[test]
+    fn gradient_without_stops_is_transparent() {
+        assert_eq!(Gradient::linear(0.0, &[]).stops(), &[(0.0, [0.0; 4])]);
     }
 }
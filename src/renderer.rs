@@ -1,9 +1,30 @@
-use crate::quad::QuadRenderer;
+use crate::font::{FontRegistry, TextStyle};
+use crate::layout::Bbox;
+use crate::quad::{QuadRenderer, QuadStyle};
 use crate::text_renderer::TextRenderer;
 use crate::textured_quad::TexturedQuadRenderer;
 use std::iter;
+use std::ops::Range;
 use winit::window::Window;
 
+/// Which renderer draws a batch.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Primitive {
+    Quad,
+    TexturedQuad,
+    Text,
+}
+
+/// A run of things drawn by the same renderer, one after the other. The range
+/// is of instances for quads and of indices for text.
+#[derive(Debug, Clone)]
+struct Batch {
+    primitive: Primitive,
+    range: Range<u32>,
+    /// Nothing outside of this is drawn.
+    clip: Option<Bbox>,
+}
+
 pub struct State<'window> {
     surface: wgpu::Surface<'window>,
     device: wgpu::Device,
@@ -12,13 +33,21 @@ pub struct State<'window> {
     pub size: winit::dpi::PhysicalSize<u32>,
     pub window: &'window Window,
 
-    pub quad_renderer: QuadRenderer,
-    pub textured_quad_renderer: TexturedQuadRenderer,
-    pub text_renderer: TextRenderer,
+    quad_renderer: QuadRenderer,
+    textured_quad_renderer: TexturedQuadRenderer,
+    /// Also used for measuring text. Draw text through `State` so it's
+    /// painted in order.
+    pub(crate) text_renderer: TextRenderer,
+
+    /// Everything drawn this frame, in paint order.
+    batches: Vec<Batch>,
+    /// The current clip is the last one, already intersected with all of the
+    /// ones before it.
+    clips: Vec<Bbox>,
 }
 
 impl<'window> State<'window> {
-    pub async fn new(window: &'window Window) -> Self {
+    pub async fn new(window: &'window Window, fonts: FontRegistry) -> Self {
         let size = window.inner_size();
 
         let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
@@ -77,7 +106,7 @@ impl<'window> State<'window> {
 
         let quad_renderer = QuadRenderer::new(&device, &config.format, size);
 
-        let text_renderer = TextRenderer::new(&device, &queue, &config.format, size);
+        let text_renderer = TextRenderer::new(&device, &queue, &config.format, size, fonts);
 
         let textured_quad_renderer =
             TexturedQuadRenderer::new(&device, &queue, &config.format, size);
@@ -93,6 +122,9 @@ impl<'window> State<'window> {
             quad_renderer,
             textured_quad_renderer,
             text_renderer,
+
+            batches: Vec::new(),
+            clips: Vec::new(),
         }
     }
 
@@ -109,14 +141,159 @@ impl<'window> State<'window> {
         self.quad_renderer.clear();
         self.textured_quad_renderer.clear();
         self.text_renderer.clear();
+        self.batches.clear();
+        self.clips.clear();
+    }
+
+    /// Clip everything added until the matching `pop_clip` to the bbox. Clips
+    /// nest, so this can only shrink the visible area.
+    pub fn push_clip(&mut self, bbox: &Bbox) {
+        let clip = match self.clips.last() {
+            Some(current) => current.intersect(bbox),
+            None => *bbox,
+        };
+        self.clips.push(clip);
+    }
+
+    pub fn pop_clip(&mut self) {
+        self.clips.pop();
+    }
+
+    /// Things are painted in the order they're added, so later ones end up on
+    /// top. Consecutive things from the same renderer are merged into one draw
+    /// call.
+    fn record(&mut self, primitive: Primitive, range: Range<u32>) {
+        if range.is_empty() {
+            return;
+        }
+        let clip = self.clips.last().copied();
+        if let Some(last) = self.batches.last_mut() {
+            if last.primitive == primitive && last.range.end == range.start && last.clip == clip {
+                last.range.end = range.end;
+                return;
+            }
+        }
+        self.batches.push(Batch {
+            primitive,
+            range,
+            clip,
+        });
+    }
+
+    /// Add a quad filling the bbox. Takes either a `QuadStyle` or a plain
+    /// color.
+    pub fn add_quad(&mut self, style: impl Into<QuadStyle>, bbox: &Bbox) {
+        let start = self.quad_renderer.instance_count();
+        self.quad_renderer.add_instance(style, bbox);
+        self.record(Primitive::Quad, start..self.quad_renderer.instance_count());
+    }
+
+    /// Add an unfilled rectangle with edges `width` pixels thick, drawn just
+    /// inside of the bbox.
+    pub fn add_outline(&mut self, color: [f32; 4], bbox: &Bbox, width: f32) {
+        let start = self.quad_renderer.instance_count();
+        self.quad_renderer.add_outline(color, bbox, width);
+        self.record(Primitive::Quad, start..self.quad_renderer.instance_count());
+    }
+
+    pub fn add_textured_quad(&mut self, bbox: &Bbox) {
+        let start = self.textured_quad_renderer.instance_count();
+        self.textured_quad_renderer.add_instance(bbox);
+        let end = self.textured_quad_renderer.instance_count();
+        self.record(Primitive::TexturedQuad, start..end);
+    }
+
+    /// Add a line of text with its top left corner at (x, y). See
+    /// `TextRenderer::add_string_to_batch`.
+    pub fn add_text(
+        &mut self,
+        text: &str,
+        style: &TextStyle,
+        x: f32,
+        y: f32,
+        text_color: [f32; 4],
+        wrap_bbox: Option<&Bbox>,
+    ) -> f32 {
+        let start = self.text_renderer.index_count();
+        let height = self.text_renderer.add_string_to_batch(
+            text,
+            style,
+            &self.queue,
+            x,
+            y,
+            text_color,
+            wrap_bbox,
+        );
+        self.record(Primitive::Text, start..self.text_renderer.index_count());
+        height
+    }
+
+    /// Add a line of text centered on (x, y).
+    pub fn add_text_centered(
+        &mut self,
+        text: &str,
+        style: &TextStyle,
+        x: f32,
+        y: f32,
+        text_color: [f32; 4],
+    ) {
+        let start = self.text_renderer.index_count();
+        self.text_renderer
+            .add_string_to_batch_centered(text, style, &self.queue, x, y, text_color);
+        self.record(Primitive::Text, start..self.text_renderer.index_count());
+    }
+
+    /// Add several lines of text, the first with its top left corner at
+    /// (x, y).
+    pub fn add_multiline_text(
+        &mut self,
+        text: &Vec<String>,
+        style: &TextStyle,
+        x: f32,
+        y: f32,
+        text_color: [f32; 4],
+        wrap_bbox: Option<&Bbox>,
+    ) {
+        let start = self.text_renderer.index_count();
+        self.text_renderer.add_multiline_string_to_batch(
+            text,
+            style,
+            &self.queue,
+            x,
+            y,
+            text_color,
+            wrap_bbox,
+        );
+        self.record(Primitive::Text, start..self.text_renderer.index_count());
     }
 
     pub fn update(&mut self) {
         let window_size = self.window.inner_size();
 
-        self.quad_renderer.update(window_size, &self.queue);
-        self.textured_quad_renderer.update(window_size, &self.queue);
-        self.text_renderer.update(window_size, &self.queue);
+        self.quad_renderer
+            .update(window_size, &self.device, &self.queue);
+        self.textured_quad_renderer
+            .update(window_size, &self.device, &self.queue);
+        self.text_renderer
+            .update(window_size, &self.device, &self.queue);
+    }
+
+    /// The clip in the render target's pixels, measured from the top left and
+    /// kept inside of the target. `None` if nothing would be visible.
+    fn scissor_rect(&self, clip: Option<Bbox>) -> Option<(u32, u32, u32, u32)> {
+        let (target_width, target_height) = (self.config.width, self.config.height);
+        let Some(clip) = clip else {
+            return Some((0, 0, target_width, target_height));
+        };
+
+        let x0 = (clip.min.0.floor().max(0.0) as u32).min(target_width);
+        let x1 = (clip.max.0.ceil().max(0.0) as u32).min(target_width);
+        let y0 = ((target_height as f32 - clip.max.1).floor().max(0.0) as u32).min(target_height);
+        let y1 = ((target_height as f32 - clip.min.1).ceil().max(0.0) as u32).min(target_height);
+        if x1 <= x0 || y1 <= y0 {
+            return None;
+        }
+        Some((x0, y0, x1 - x0, y1 - y0))
     }
 
     pub fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
@@ -147,9 +324,21 @@ impl<'window> State<'window> {
                 occlusion_query_set: None,
             });
 
-            self.quad_renderer.render(&mut render_pass);
-            self.textured_quad_renderer.render(&mut render_pass);
-            self.text_renderer.render(&mut render_pass);
+            for batch in &self.batches {
+                let Some((x, y, width, height)) = self.scissor_rect(batch.clip) else {
+                    continue;
+                };
+                render_pass.set_scissor_rect(x, y, width, height);
+
+                let range = batch.range.clone();
+                match batch.primitive {
+                    Primitive::Quad => self.quad_renderer.render(&mut render_pass, range),
+                    Primitive::TexturedQuad => {
+                        self.textured_quad_renderer.render(&mut render_pass, range)
+                    }
+                    Primitive::Text => self.text_renderer.render(&mut render_pass, range),
+                }
+            }
         }
 
         self.queue.submit(iter::once(encoder.finish()));
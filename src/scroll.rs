@@ -0,0 +1,609 @@
+use crate::{
+    event::{EventStatus, PointerEvent},
+    layout::{Bbox, Element, LayoutNode, Size, TextMeasure},
+    quad::QuadStyle,
+    renderer::State,
+};
+use winit::event::MouseButton;
+
+/// How the scrollbars of a `Scroll` are drawn. Sizes are in pixels.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScrollbarStyle {
+    /// How thick the bars are. They're drawn over the edges of the content.
+    pub width: f32,
+    /// The thumb never gets shorter than this, however long the content is.
+    pub min_thumb_length: f32,
+    pub track: QuadStyle,
+    pub thumb: QuadStyle,
+    /// The thumb while it's being dragged.
+    pub thumb_active: QuadStyle,
+}
+
+impl Default for ScrollbarStyle {
+    fn default() -> Self {
+        Self {
+            width: 8.0,
+            min_thumb_length: 24.0,
+            track: QuadStyle::new([0.0, 0.0, 0.0, 0.2]).with_radius(4.0),
+            thumb: QuadStyle::new([0.5, 0.5, 0.55, 0.6]).with_radius(4.0),
+            thumb_active: QuadStyle::new([0.65, 0.65, 0.7, 0.8]).with_radius(4.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Axis {
+    Horizontal,
+    Vertical,
+}
+
+impl Axis {
+    fn get(self, v: (f32, f32)) -> f32 {
+        match self {
+            Axis::Horizontal => v.0,
+            Axis::Vertical => v.1,
+        }
+    }
+
+    fn set(self, v: &mut (f32, f32), value: f32) {
+        match self {
+            Axis::Horizontal => v.0 = value,
+            Axis::Vertical => v.1 = value,
+        }
+    }
+
+    fn length(self, bbox: &Bbox) -> f32 {
+        match self {
+            Axis::Horizontal => bbox.width(),
+            Axis::Vertical => bbox.height(),
+        }
+    }
+
+    /// How far `pos` is along the track from its start, which is the left of
+    /// a horizontal track and the top of a vertical one.
+    fn along(self, track: &Bbox, pos: (f32, f32)) -> f32 {
+        match self {
+            Axis::Horizontal => pos.0 - track.min.0,
+            Axis::Vertical => track.max.1 - pos.1,
+        }
+    }
+}
+
+/// A scrollbar thumb being dragged. `grab` is how far from the start of the
+/// thumb it was picked up.
+#[derive(Debug, Clone, Copy)]
+struct Drag {
+    axis: Axis,
+    grab: f32,
+}
+
+/// The scroll offset and the scrollbars that move it, shared by everything
+/// that scrolls. `max` is how far the content can be scrolled along each axis,
+/// and `viewport` is the area the content is seen through.
+#[derive(Debug, Default)]
+pub(crate) struct Scrollbars {
+    /// How far the content is scrolled, right and down, in pixels. Kept in
+    /// range against the latest layout whenever it's used.
+    pub(crate) offset: (f32, f32),
+    drag: Option<Drag>,
+}
+
+impl Scrollbars {
+    pub(crate) fn clamped(&self, max: (f32, f32)) -> (f32, f32) {
+        (
+            self.offset.0.clamp(0.0, max.0),
+            self.offset.1.clamp(0.0, max.1),
+        )
+    }
+
+    /// Scroll to `offset`, kept in range.
+    pub(crate) fn set(&mut self, offset: (f32, f32), max: (f32, f32)) {
+        self.offset = offset;
+        self.offset = self.clamped(max);
+    }
+
+    pub(crate) fn draw(
+        &self,
+        state: &mut State,
+        style: &ScrollbarStyle,
+        viewport: &Bbox,
+        max: (f32, f32),
+    ) {
+        for axis in [Axis::Horizontal, Axis::Vertical] {
+            if let Some((track, thumb)) = self.bar(style, viewport, max, axis) {
+                state.add_quad(style.track.clone(), &track);
+                let thumb_style = match self.drag {
+                    Some(drag) if drag.axis == axis => &style.thumb_active,
+                    _ => &style.thumb,
+                };
+                state.add_quad(thumb_style.clone(), &thumb);
+            }
+        }
+    }
+
+    /// Whether `pos` is over one of the scrollbars. They're drawn over the
+    /// content, so they get to handle the pointer before it does.
+    pub(crate) fn on_scrollbar(
+        &self,
+        style: &ScrollbarStyle,
+        viewport: &Bbox,
+        max: (f32, f32),
+        pos: (f32, f32),
+    ) -> bool {
+        [Axis::Horizontal, Axis::Vertical]
+            .into_iter()
+            .filter_map(|axis| self.bar(style, viewport, max, axis))
+            .any(|(track, _)| track.inside(pos))
+    }
+
+    pub(crate) fn handle_pointer(
+        &mut self,
+        style: &ScrollbarStyle,
+        viewport: &Bbox,
+        max: (f32, f32),
+        event: &PointerEvent,
+    ) -> EventStatus {
+        match *event {
+            PointerEvent::Scrolled { delta, .. } => {
+                let current = self.clamped(max);
+                self.set((current.0 - delta.0, current.1 - delta.1), max);
+                // Let a scroll further out have it when this one is already at
+                // the end.
+                if self.offset == current {
+                    EventStatus::Ignored
+                } else {
+                    EventStatus::Handled
+                }
+            }
+            PointerEvent::Pressed {
+                pos,
+                button: MouseButton::Left,
+            } => {
+                for axis in [Axis::Horizontal, Axis::Vertical] {
+                    let Some((track, thumb)) = self.bar(style, viewport, max, axis) else {
+                        continue;
+                    };
+                    if thumb.inside(pos) {
+                        let grab = axis.along(&track, pos) - axis.along(&track, thumb.top_left());
+                        self.drag = Some(Drag { axis, grab });
+                        return EventStatus::Handled;
+                    }
+                    if track.inside(pos) {
+                        // Clicking the track moves a page towards the pointer.
+                        let page = axis.length(viewport);
+                        let towards_start =
+                            axis.along(&track, pos) < axis.along(&track, thumb.top_left());
+                        let current = axis.get(self.clamped(max));
+                        let offset = if towards_start {
+                            current - page
+                        } else {
+                            current + page
+                        };
+                        axis.set(&mut self.offset, offset.clamp(0.0, axis.get(max)));
+                        return EventStatus::Handled;
+                    }
+                }
+                EventStatus::Ignored
+            }
+            PointerEvent::Moved { pos } => {
+                let Some(drag) = self.drag else {
+                    return EventStatus::Ignored;
+                };
+                let Some((track, thumb)) = self.bar(style, viewport, max, drag.axis) else {
+                    return EventStatus::Handled;
+                };
+                let travel = axis_travel(drag.axis, &track, &thumb);
+                if travel > 0.0 {
+                    let start = (drag.axis.along(&track, pos) - drag.grab).clamp(0.0, travel);
+                    let offset = start / travel * drag.axis.get(max);
+                    drag.axis.set(&mut self.offset, offset);
+                }
+                EventStatus::Handled
+            }
+            PointerEvent::Released {
+                button: MouseButton::Left,
+                ..
+            } if self.drag.is_some() => {
+                self.drag = None;
+                EventStatus::Handled
+            }
+            _ => EventStatus::Ignored,
+        }
+    }
+
+    /// The track and thumb of the scrollbar along `axis`, if the content
+    /// doesn't fit that way. The bars run along the right and bottom edges of
+    /// the viewport, leaving the corner empty when both are showing.
+    fn bar(
+        &self,
+        style: &ScrollbarStyle,
+        viewport: &Bbox,
+        max: (f32, f32),
+        axis: Axis,
+    ) -> Option<(Bbox, Bbox)> {
+        if axis.get(max) <= 0.0 {
+            return None;
+        }
+
+        let width = style.width;
+        let corner = if max.0 > 0.0 && max.1 > 0.0 {
+            width
+        } else {
+            0.0
+        };
+        let track = match axis {
+            Axis::Horizontal => Bbox::new(
+                viewport.min.0,
+                viewport.min.1,
+                viewport.max.0 - corner,
+                viewport.min.1 + width,
+            ),
+            Axis::Vertical => Bbox::new(
+                viewport.max.0 - width,
+                viewport.min.1 + corner,
+                viewport.max.0,
+                viewport.max.1,
+            ),
+        };
+
+        // The thumb is to the track what the viewport is to the content.
+        let track_length = axis.length(&track);
+        let viewport_length = axis.length(viewport);
+        let length = (track_length * viewport_length / (viewport_length + axis.get(max)))
+            .max(style.min_thumb_length)
+            .min(track_length);
+        let offset = axis.get(self.clamped(max));
+        let start = (track_length - length) * offset / axis.get(max);
+        let thumb = match axis {
+            Axis::Horizontal => Bbox::new(
+                track.min.0 + start,
+                track.min.1,
+                track.min.0 + start + length,
+                track.max.1,
+            ),
+            Axis::Vertical => Bbox::new(
+                track.min.0,
+                track.max.1 - start - length,
+                track.max.0,
+                track.max.1 - start,
+            ),
+        };
+        Some((track, thumb))
+    }
+}
+
+/// A viewport onto a child that can be bigger than it. The child is laid out
+/// at its natural size along the axes that scroll, and at the size of the
+/// viewport along the others, then moved by the scroll offset.
+///
+/// Scrolls with the mouse wheel or touchpad, and by dragging or clicking the
+/// scrollbars, which only show up when the child doesn't fit.
+#[derive(Debug)]
+pub struct Scroll {
+    /// Always exactly one element, kept in a `Vec` so it can be walked like
+    /// the children of a box.
+    elements: Vec<Element>,
+    horizontal: bool,
+    vertical: bool,
+    pub style: ScrollbarStyle,
+    scrollbars: Scrollbars,
+}
+
+impl Scroll {
+    /// Scrolls vertically only, like most lists and documents.
+    pub fn new(child: impl Into<Element>) -> Self {
+        Self {
+            elements: vec![child.into()],
+            horizontal: false,
+            vertical: true,
+            style: ScrollbarStyle::default(),
+            scrollbars: Scrollbars::default(),
+        }
+    }
+
+    pub fn with_horizontal(mut self, horizontal: bool) -> Self {
+        self.horizontal = horizontal;
+        self
+    }
+
+    pub fn with_vertical(mut self, vertical: bool) -> Self {
+        self.vertical = vertical;
+        self
+    }
+
+    pub fn with_style(mut self, style: ScrollbarStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub(crate) fn elements(&self) -> &Vec<Element> {
+        &self.elements
+    }
+
+    pub(crate) fn elements_mut(&mut self) -> &mut Vec<Element> {
+        &mut self.elements
+    }
+
+    pub fn child(&self) -> &Element {
+        &self.elements[0]
+    }
+
+    pub fn child_mut(&mut self) -> &mut Element {
+        &mut self.elements[0]
+    }
+
+    /// How far the child is scrolled, right and down, in pixels.
+    pub fn offset(&self) -> (f32, f32) {
+        self.scrollbars.offset
+    }
+
+    /// Scroll to `offset`, right and down in pixels. Offsets past the end of
+    /// the child are pulled back in when it's next laid out.
+    pub fn scroll_to(&mut self, offset: (f32, f32)) {
+        self.scrollbars.offset = offset;
+    }
+
+    /// Scroll as little as possible to make `target` visible, using the
+    /// scroll's own layout. When the target is bigger than the viewport its
+    /// top left corner is shown.
+    ///
+    /// Returns how far anything inside the scroll moved, in the same space as
+    /// `Bbox`.
+    pub fn scroll_into_view(&mut self, node: &LayoutNode, target: &Bbox) -> (f32, f32) {
+        let viewport = node.content;
+        let max = self.max_offset(node);
+        let current = self.scrollbars.clamped(max);
+        let mut offset = current;
+
+        if self.horizontal {
+            if target.min.0 < viewport.min.0 {
+                offset.0 -= viewport.min.0 - target.min.0;
+            } else if target.max.0 > viewport.max.0 {
+                offset.0 += (target.max.0 - viewport.max.0).min(target.min.0 - viewport.min.0);
+            }
+        }
+        if self.vertical {
+            if target.max.1 > viewport.max.1 {
+                offset.1 -= target.max.1 - viewport.max.1;
+            } else if target.min.1 < viewport.min.1 {
+                offset.1 += (viewport.min.1 - target.min.1).min(viewport.max.1 - target.max.1);
+            }
+        }
+
+        self.scrollbars.set(offset, max);
+        let offset = self.scrollbars.offset;
+        (current.0 - offset.0, offset.1 - current.1)
+    }
+
+    /// Lay the child out inside of the viewport, moved by the scroll offset.
+    pub(crate) fn layout(&self, measure: &mut dyn TextMeasure, viewport: Bbox) -> Vec<LayoutNode> {
+        let child = self.child();
+        let margin = child.margin;
+        let available = (
+            (viewport.width() - margin.horizontal()).max(0.0),
+            (viewport.height() - margin.vertical()).max(0.0),
+        );
+
+        let fit = if self.horizontal || self.vertical {
+            child.fit_size(measure, (!self.horizontal).then_some(available.0))
+        } else {
+            (0.0, 0.0)
+        };
+        let width = if self.horizontal {
+            natural_size(child.width, available.0, fit.0)
+        } else {
+            available.0
+        };
+        let height = if self.vertical {
+            natural_size(child.height, available.1, fit.1)
+        } else {
+            available.1
+        };
+
+        let max = (
+            (width + margin.horizontal() - viewport.width()).max(0.0),
+            (height + margin.vertical() - viewport.height()).max(0.0),
+        );
+        let offset = self.scrollbars.clamped(max);
+        let left = viewport.min.0 + margin.left - offset.0;
+        let top = viewport.max.1 - margin.top + offset.1;
+        vec![child.layout(measure, Bbox::new(left, top - height, left + width, top))]
+    }
+
+    pub(crate) fn draw(&self, state: &mut State, node: &LayoutNode) {
+        state.push_clip(&node.content);
+        self.child().draw(state, &node.children[0]);
+        state.pop_clip();
+
+        let max = self.max_offset(node);
+        self.scrollbars.draw(state, &self.style, &node.content, max);
+    }
+
+    /// The natural size of a scroll is its child's along the axes that don't
+    /// scroll, and nothing along the ones that do.
+    pub(crate) fn fit_size(
+        &self,
+        measure: &mut dyn TextMeasure,
+        max_width: Option<f32>,
+    ) -> (f32, f32) {
+        let child = self.child();
+        let max_width = match max_width {
+            Some(width) if !self.horizontal => Some((width - child.margin.horizontal()).max(0.0)),
+            _ => None,
+        };
+        let fit = child.fit_size(measure, max_width);
+        (
+            if self.horizontal {
+                0.0
+            } else {
+                fit.0 + child.margin.horizontal()
+            },
+            if self.vertical {
+                0.0
+            } else {
+                fit.1 + child.margin.vertical()
+            },
+        )
+    }
+
+    pub(crate) fn on_scrollbar(&self, node: &LayoutNode, pos: (f32, f32)) -> bool {
+        let max = self.max_offset(node);
+        self.scrollbars
+            .on_scrollbar(&self.style, &node.content, max, pos)
+    }
+
+    pub(crate) fn handle_pointer(
+        &mut self,
+        node: &LayoutNode,
+        event: &PointerEvent,
+    ) -> EventStatus {
+        let max = self.max_offset(node);
+        self.scrollbars
+            .handle_pointer(&self.style, &node.content, max, event)
+    }
+
+    /// How far the child can be scrolled along each axis.
+    fn max_offset(&self, node: &LayoutNode) -> (f32, f32) {
+        let Some(child) = node.children.first() else {
+            return (0.0, 0.0);
+        };
+        let margin = self.child().margin;
+        let viewport = node.content;
+        (
+            if self.horizontal {
+                (child.bbox.width() + margin.horizontal() - viewport.width()).max(0.0)
+            } else {
+                0.0
+            },
+            if self.vertical {
+                (child.bbox.height() + margin.vertical() - viewport.height()).max(0.0)
+            } else {
+                0.0
+            },
+        )
+    }
+}
+
+/// How far the thumb can move along the track.
+fn axis_travel(axis: Axis, track: &Bbox, thumb: &Bbox) -> f32 {
+    axis.length(track) - axis.length(thumb)
+}
+
+/// The size of the child along an axis that scrolls. Flex children fill the
+/// viewport when they're smaller than it.
+fn natural_size(size: Size, available: f32, fit: f32) -> f32 {
+    match size {
+        Size::Fixed(size) => size,
+        Size::Percent(percent) => available * percent / 100.0,
+        Size::Fit => fit,
+        Size::Flex(_) => fit.max(available),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::{
+        tests::{wrapped, FixedMeasure},
+        Thing, Vbox,
+    };
+
+    /// A vbox of `rows` rows, each 20 pixels tall.
+    fn rows(rows: usize) -> Element {
+        let rows = (0..rows)
+            .map(|_| Element::new(wrapped("row")).with_height(Size::Fit))
+            .collect();
+        Element::new(Thing::Vbox(Vbox::new(rows)))
+    }
+
+    fn viewport() -> Bbox {
+        Bbox::new(0.0, 0.0, 100.0, 100.0)
+    }
+
+    #[test]
+    fn child_is_laid_out_at_its_natural_height() {
+        let scroll = Scroll::new(rows(10));
+        let nodes = scroll.layout(&mut FixedMeasure, viewport());
+
+        assert_eq!(nodes[0].bbox, Bbox::new(0.0, -100.0, 100.0, 100.0));
+    }
+
+    #[test]
+    fn flex_child_fills_a_bigger_viewport() {
+        let scroll = Scroll::new(rows(2));
+        let nodes = scroll.layout(&mut FixedMeasure, viewport());
+
+        assert_eq!(nodes[0].bbox, viewport());
+    }
+
+    #[test]
+    fn offset_moves_the_child_and_is_clamped() {
+        let mut scroll = Scroll::new(rows(10));
+        scroll.scroll_to((0.0, 30.0));
+        let nodes = scroll.layout(&mut FixedMeasure, viewport());
+        assert_eq!(nodes[0].bbox, Bbox::new(0.0, -70.0, 100.0, 130.0));
+
+        scroll.scroll_to((50.0, 500.0));
+        let nodes = scroll.layout(&mut FixedMeasure, viewport());
+        assert_eq!(nodes[0].bbox, Bbox::new(0.0, 0.0, 100.0, 200.0));
+    }
+
+    #[test]
+    fn horizontal_scroll_uses_the_unwrapped_width() {
+        let scroll = Scroll::new(Element::new(wrapped("a long line of text")))
+            .with_horizontal(true)
+            .with_vertical(false);
+        let nodes = scroll.layout(&mut FixedMeasure, viewport());
+
+        assert_eq!(nodes[0].bbox, Bbox::new(0.0, 0.0, 190.0, 100.0));
+    }
+
+    #[test]
+    fn wheel_scrolls_until_the_end() {
+        let mut scroll = Scroll::new(rows(10));
+        let node = Element::new(Thing::Scroll(Scroll::new(rows(10))))
+            .layout(&mut FixedMeasure, viewport());
+        let wheel = |delta| PointerEvent::Scrolled {
+            pos: (50.0, 50.0),
+            delta: (0.0, delta),
+        };
+
+        assert_eq!(
+            scroll.handle_pointer(&node, &wheel(-80.0)),
+            EventStatus::Handled
+        );
+        assert_eq!(scroll.offset(), (0.0, 80.0));
+        assert_eq!(
+            scroll.handle_pointer(&node, &wheel(-80.0)),
+            EventStatus::Handled
+        );
+        assert_eq!(scroll.offset(), (0.0, 100.0));
+        assert_eq!(
+            scroll.handle_pointer(&node, &wheel(-80.0)),
+            EventStatus::Ignored
+        );
+    }
+
+    #[test]
+    fn scroll_into_view_moves_as_little_as_possible() {
+        let mut scroll = Scroll::new(rows(10));
+        let node = Element::new(Thing::Scroll(Scroll::new(rows(10))))
+            .layout(&mut FixedMeasure, viewport());
+
+        // The sixth row is just below the viewport.
+        let moved = scroll.scroll_into_view(&node, &Bbox::new(0.0, -20.0, 100.0, 0.0));
+        assert_eq!(moved, (0.0, 20.0));
+        assert_eq!(scroll.offset(), (0.0, 20.0));
+
+        // Already visible, so nothing moves.
+        let moved = scroll.scroll_into_view(&node, &Bbox::new(0.0, 40.0, 100.0, 60.0));
+        assert_eq!(moved, (0.0, 0.0));
+    }
+
+    #[test]
+    fn fit_size_is_zero_along_scrolling_axes() {
+        let scroll = Scroll::new(rows(10));
+
+        assert_eq!(scroll.fit_size(&mut FixedMeasure, Some(100.0)), (30.0, 0.0));
+    }
+}
@@ -0,0 +1,497 @@
+use crate::{
+    event::{EventContext, EventStatus, KeyboardEvent, PointerEvent},
+    font::TextStyle,
+    layout::{Bbox, LayoutNode, TextMeasure},
+    quad::QuadStyle,
+    renderer::State,
+};
+use std::{cell::Cell, fmt, ops::Range};
+use unicode_segmentation::UnicodeSegmentation;
+use winit::{
+    event::MouseButton,
+    keyboard::{Key, NamedKey},
+};
+
+const CARET_WIDTH: f32 = 1.0;
+
+/// How a text input is drawn.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextInputStyle {
+    pub background: QuadStyle,
+    pub text_color: [f32; 4],
+    pub placeholder_color: [f32; 4],
+    pub selection_color: [f32; 4],
+    pub caret_color: [f32; 4],
+}
+
+impl Default for TextInputStyle {
+    fn default() -> Self {
+        Self {
+            background: QuadStyle::new([0.12, 0.12, 0.14, 1.0])
+                .with_radius(4.0)
+                .with_border(1.0, [0.3, 0.3, 0.35, 1.0]),
+            text_color: [1.0, 1.0, 1.0, 1.0],
+            placeholder_color: [0.5, 0.5, 0.55, 1.0],
+            selection_color: [0.2, 0.35, 0.6, 1.0],
+            caret_color: [1.0, 1.0, 1.0, 1.0],
+        }
+    }
+}
+
+struct ChangeHandler(Box<dyn FnMut(&str)>);
+
+impl fmt::Debug for ChangeHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ChangeHandler")
+    }
+}
+
+/// A single line of editable text.
+///
+/// The caret and the selection anchor are byte offsets into the text, always
+/// on a char boundary. Anything between the two is selected. The keyboard
+/// moves and deletes a grapheme cluster at a time, so a letter and the marks
+/// on it are never split.
+#[derive(Debug)]
+pub struct TextInput {
+    pub placeholder: String,
+    pub text_style: TextStyle,
+    pub style: TextInputStyle,
+    text: String,
+    caret: usize,
+    anchor: usize,
+    focused: bool,
+    selecting: bool,
+    /// How far the text is scrolled to the left, in pixels. Only known once
+    /// the text is measured, so it's kept up to date while drawing to keep
+    /// the caret in view.
+    scroll: Cell<f32>,
+    on_change: Option<ChangeHandler>,
+}
+
+impl TextInput {
+    pub fn new(placeholder: &str) -> Self {
+        Self {
+            placeholder: placeholder.to_string(),
+            text_style: TextStyle::default(),
+            style: TextInputStyle::default(),
+            text: String::new(),
+            caret: 0,
+            anchor: 0,
+            focused: false,
+            selecting: false,
+            scroll: Cell::new(0.0),
+            on_change: None,
+        }
+    }
+
+    pub fn with_text(mut self, text: &str) -> Self {
+        self.set_text(text);
+        self
+    }
+
+    pub fn with_text_style(mut self, text_style: TextStyle) -> Self {
+        self.text_style = text_style;
+        self
+    }
+
+    pub fn with_style(mut self, style: TextInputStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Run `on_change` with the new text every time the user edits it.
+    pub fn with_on_change(mut self, on_change: impl FnMut(&str) + 'static) -> Self {
+        self.on_change = Some(ChangeHandler(Box::new(on_change)));
+        self
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replace the text, moving the caret to the end.
+    pub fn set_text(&mut self, text: &str) {
+        self.text = text.to_string();
+        self.caret = self.text.len();
+        self.anchor = self.caret;
+    }
+
+    /// The selected byte range. Empty when nothing is selected.
+    pub fn selection(&self) -> Range<usize> {
+        self.caret.min(self.anchor)..self.caret.max(self.anchor)
+    }
+
+    pub fn selected_text(&self) -> &str {
+        &self.text[self.selection()]
+    }
+
+    pub(crate) fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+        if !focused {
+            self.selecting = false;
+        }
+    }
+
+    fn prev_boundary(&self, i: usize) -> usize {
+        self.text[..i]
+            .grapheme_indices(true)
+            .next_back()
+            .map_or(0, |(j, _)| j)
+    }
+
+    fn next_boundary(&self, i: usize) -> usize {
+        self.text[i..]
+            .graphemes(true)
+            .next()
+            .map_or(i, |grapheme| i + grapheme.len())
+    }
+
+    /// Move the caret, growing the selection if `extend` is set and dropping
+    /// it otherwise.
+    fn move_caret(&mut self, to: usize, extend: bool) {
+        self.caret = to;
+        if !extend {
+            self.anchor = to;
+        }
+    }
+
+    /// Returns false if there was nothing selected.
+    fn delete_selection(&mut self) -> bool {
+        let selection = self.selection();
+        if selection.is_empty() {
+            return false;
+        }
+
+        self.text.replace_range(selection.clone(), "");
+        self.move_caret(selection.start, false);
+        true
+    }
+
+    fn insert(&mut self, s: &str) {
+        self.delete_selection();
+        self.text.insert_str(self.caret, s);
+        self.move_caret(self.caret + s.len(), false);
+    }
+
+    /// The char boundary closest to `x` pixels from the start of the text.
+    fn index_at(&self, x: f32, measure: &mut dyn TextMeasure) -> usize {
+        measure
+            .caret_positions(&self.text, &self.text_style)
+            .into_iter()
+            .min_by(|a, b| (a.1 - x).abs().total_cmp(&(b.1 - x).abs()))
+            .map_or(0, |(i, _)| i)
+    }
+
+    pub(crate) fn handle_pointer(
+        &mut self,
+        node: &LayoutNode,
+        event: &PointerEvent,
+        cx: &mut EventContext,
+    ) -> EventStatus {
+        match event {
+            PointerEvent::Pressed {
+                pos,
+                button: MouseButton::Left,
+            } => {
+                let x = pos.0 - node.content.min.0 + self.scroll.get();
+                let index = self.index_at(x, cx.measure);
+                self.move_caret(index, false);
+                self.selecting = true;
+                EventStatus::Handled
+            }
+            PointerEvent::Moved { pos } if self.selecting => {
+                let x = pos.0 - node.content.min.0 + self.scroll.get();
+                let index = self.index_at(x, cx.measure);
+                self.move_caret(index, true);
+                EventStatus::Handled
+            }
+            PointerEvent::Released {
+                button: MouseButton::Left,
+                ..
+            } => {
+                self.selecting = false;
+                EventStatus::Handled
+            }
+            _ => EventStatus::Ignored,
+        }
+    }
+
+    pub(crate) fn handle_key(&mut self, event: &KeyboardEvent) -> EventStatus {
+        let shift = event.modifiers.shift_key();
+        let before = self.text.len();
+        let mut edited = false;
+
+        match &event.key {
+            Key::Named(NamedKey::ArrowLeft) => {
+                let selection = self.selection();
+                if !shift && !selection.is_empty() {
+                    self.move_caret(selection.start, false);
+                } else {
+                    self.move_caret(self.prev_boundary(self.caret), shift);
+                }
+            }
+            Key::Named(NamedKey::ArrowRight) => {
+                let selection = self.selection();
+                if !shift && !selection.is_empty() {
+                    self.move_caret(selection.end, false);
+                } else {
+                    self.move_caret(self.next_boundary(self.caret), shift);
+                }
+            }
+            Key::Named(NamedKey::Home) => self.move_caret(0, shift),
+            Key::Named(NamedKey::End) => self.move_caret(self.text.len(), shift),
+            Key::Named(NamedKey::Backspace) => {
+                if !self.delete_selection() && self.caret > 0 {
+                    self.anchor = self.prev_boundary(self.caret);
+                    self.delete_selection();
+                }
+                edited = self.text.len() != before;
+            }
+            Key::Named(NamedKey::Delete) => {
+                if !self.delete_selection() && self.caret < self.text.len() {
+                    self.anchor = self.next_boundary(self.caret);
+                    self.delete_selection();
+                }
+                edited = self.text.len() != before;
+            }
+            _ => {
+                let modifiers = event.modifiers;
+                let text = match &event.text {
+                    Some(text) if !modifiers.control_key() && !modifiers.alt_key() => text,
+                    _ => return EventStatus::Ignored,
+                };
+                if text.chars().any(char::is_control) {
+                    return EventStatus::Ignored;
+                }
+                self.insert(text);
+                edited = true;
+            }
+        }
+
+        if edited {
+            if let Some(on_change) = &mut self.on_change {
+                (on_change.0)(&self.text);
+            }
+        }
+        EventStatus::Handled
+    }
+
+    pub(crate) fn draw(&self, state: &mut State, node: &LayoutNode) {
+        let content = node.content;
+        state.add_quad(self.style.background.clone(), &node.bbox);
+
+        // One line of text, centered vertically.
+        let line_height = state.text_renderer.line_height(&self.text_style);
+        let top = content.max.1 - (content.height() - line_height) / 2.0;
+        let bottom = top - line_height;
+
+        let positions = state
+            .text_renderer
+            .caret_positions(&self.text, &self.text_style);
+        let x_of = |index: usize| {
+            let i = positions.partition_point(|&(i, _)| i <= index);
+            positions[i.saturating_sub(1)].1
+        };
+
+        let width = (content.width() - CARET_WIDTH).max(0.0);
+        let caret = x_of(self.caret);
+        let text_width = positions.iter().map(|&(_, x)| x).fold(0.0, f32::max);
+        let scroll = keep_in_view(self.scroll.get(), caret, text_width, width);
+        self.scroll.set(scroll);
+        let x = content.min.0 - scroll;
+
+        state.push_clip(&content);
+        if self.focused {
+            let selection = self.selection();
+            if !selection.is_empty() {
+                let (start, end) = (x_of(selection.start), x_of(selection.end));
+                state.add_quad(
+                    self.style.selection_color,
+                    &Bbox::new(x + start.min(end), bottom, x + start.max(end), top),
+                );
+            }
+
+            state.add_quad(
+                self.style.caret_color,
+                &Bbox::new(x + caret, bottom, x + caret + CARET_WIDTH, top),
+            );
+        }
+
+        let (text, color) = if self.text.is_empty() {
+            (&self.placeholder, self.style.placeholder_color)
+        } else {
+            (&self.text, self.style.text_color)
+        };
+        state.add_text(text, &self.text_style, x, top, color, None);
+        state.pop_clip();
+    }
+
+    pub(crate) fn fit_size(&self, measure: &mut dyn TextMeasure) -> (f32, f32) {
+        let width = measure
+            .string_width(&self.text, &self.text_style)
+            .max(measure.string_width(&self.placeholder, &self.text_style));
+        (width + CARET_WIDTH, measure.line_height(&self.text_style))
+    }
+}
+
+/// Where to scroll text that is `text_width` wide, in a box `width` wide, to
+/// keep `caret` in view. Moves as little as possible from `scroll`, without
+/// leaving space after the end of the text.
+fn keep_in_view(scroll: f32, caret: f32, text_width: f32, width: f32) -> f32 {
+    scroll
+        .clamp(caret - width, caret)
+        .min(text_width - width)
+        .max(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::tests::FixedMeasure;
+    use std::rc::Rc;
+    use winit::keyboard::ModifiersState;
+
+    fn press(input: &mut TextInput, key: NamedKey, shift: bool) {
+        input.handle_key(&KeyboardEvent {
+            key: Key::Named(key),
+            text: None,
+            modifiers: if shift {
+                ModifiersState::SHIFT
+            } else {
+                ModifiersState::empty()
+            },
+        });
+    }
+
+    fn type_text(input: &mut TextInput, text: &str) {
+        input.handle_key(&KeyboardEvent {
+            key: Key::Character(text.into()),
+            text: Some(text.to_string()),
+            modifiers: ModifiersState::empty(),
+        });
+    }
+
+    #[test]
+    fn typing_replaces_the_selection() {
+        let mut input = TextInput::new("").with_text("hello");
+        press(&mut input, NamedKey::Home, false);
+        press(&mut input, NamedKey::ArrowRight, true);
+        press(&mut input, NamedKey::ArrowRight, true);
+        assert_eq!(input.selection(), 0..2);
+
+        type_text(&mut input, "J");
+        assert_eq!(input.text(), "Jllo");
+        assert_eq!(input.selection(), 1..1);
+    }
+
+    #[test]
+    fn backspace_and_delete_remove_one_char_or_the_selection() {
+        let mut input = TextInput::new("").with_text("abcdef");
+        press(&mut input, NamedKey::Backspace, false);
+        assert_eq!(input.text(), "abcde");
+
+        press(&mut input, NamedKey::Home, false);
+        press(&mut input, NamedKey::Delete, false);
+        assert_eq!(input.text(), "bcde");
+        assert_eq!(input.selection(), 0..0);
+
+        press(&mut input, NamedKey::End, true);
+        press(&mut input, NamedKey::ArrowLeft, true);
+        assert_eq!(input.selected_text(), "bcd");
+        press(&mut input, NamedKey::Backspace, false);
+        assert_eq!(input.text(), "e");
+
+        press(&mut input, NamedKey::End, true);
+        press(&mut input, NamedKey::Delete, false);
+        assert_eq!(input.text(), "");
+        // Nothing left to delete.
+        press(&mut input, NamedKey::Backspace, false);
+        press(&mut input, NamedKey::Delete, false);
+        assert_eq!(input.selection(), 0..0);
+    }
+
+    #[test]
+    fn arrows_collapse_the_selection_to_its_side() {
+        let mut input = TextInput::new("").with_text("abcdef");
+        press(&mut input, NamedKey::ArrowLeft, true);
+        press(&mut input, NamedKey::ArrowLeft, true);
+        assert_eq!(input.selection(), 4..6);
+
+        press(&mut input, NamedKey::ArrowLeft, false);
+        assert_eq!(input.selection(), 4..4);
+
+        press(&mut input, NamedKey::Home, true);
+        assert_eq!(input.selection(), 0..4);
+        press(&mut input, NamedKey::ArrowRight, false);
+        assert_eq!(input.selection(), 4..4);
+    }
+
+    #[test]
+    fn arrows_step_over_whole_grapheme_clusters() {
+        // An "e" with a combining acute accent, then a flag made of two chars.
+        let mut input = TextInput::new("").with_text("ae\u{301}\u{1F1EF}\u{1F1F5}b");
+        press(&mut input, NamedKey::Home, false);
+
+        let mut carets = vec![];
+        for _ in 0..5 {
+            press(&mut input, NamedKey::ArrowRight, false);
+            carets.push(input.selection().start);
+        }
+        assert_eq!(carets, vec![1, 4, 12, 13, 13]);
+
+        press(&mut input, NamedKey::ArrowLeft, false);
+        press(&mut input, NamedKey::Backspace, false);
+        assert_eq!(input.text(), "ae\u{301}b");
+    }
+
+    #[test]
+    fn on_change_runs_only_when_the_text_changes() {
+        let changes = Rc::new(Cell::new(0));
+        let counter = changes.clone();
+        let mut input = TextInput::new("")
+            .with_text("ab")
+            .with_on_change(move |_| counter.set(counter.get() + 1));
+
+        press(&mut input, NamedKey::ArrowLeft, false);
+        press(&mut input, NamedKey::Home, true);
+        press(&mut input, NamedKey::Delete, false);
+        assert_eq!(changes.get(), 1);
+
+        // Nothing before the caret, and nothing after it.
+        press(&mut input, NamedKey::Backspace, false);
+        press(&mut input, NamedKey::End, false);
+        press(&mut input, NamedKey::Delete, false);
+        assert_eq!(changes.get(), 1);
+
+        type_text(&mut input, "c");
+        press(&mut input, NamedKey::Backspace, false);
+        assert_eq!(changes.get(), 3);
+        assert_eq!(input.text(), "b");
+    }
+
+    #[test]
+    fn index_at_picks_the_closest_boundary() {
+        let input = TextInput::new("").with_text("héllo");
+        let index_at = |x| input.index_at(x, &mut FixedMeasure);
+
+        assert_eq!(index_at(-20.0), 0);
+        assert_eq!(index_at(6.0), 1);
+        assert_eq!(index_at(16.0), 3);
+        assert_eq!(index_at(500.0), 6);
+    }
+
+    #[test]
+    fn scrolls_only_to_keep_the_caret_in_view() {
+        // Text that fits never scrolls.
+        assert_eq!(keep_in_view(0.0, 50.0, 80.0, 100.0), 0.0);
+        // The caret past the right edge pulls the text left.
+        assert_eq!(keep_in_view(0.0, 150.0, 300.0, 100.0), 50.0);
+        // Moving back inside the view doesn't scroll.
+        assert_eq!(keep_in_view(50.0, 120.0, 300.0, 100.0), 50.0);
+        // Past the left edge scrolls back.
+        assert_eq!(keep_in_view(50.0, 20.0, 300.0, 100.0), 20.0);
+        // Deleting from the end doesn't leave space after the text.
+        assert_eq!(keep_in_view(200.0, 150.0, 150.0, 100.0), 50.0);
+    }
+}
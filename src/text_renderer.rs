@@ -1,27 +1,131 @@
-use crate::{layout::Bbox, texture::Texture};
+use crate::{
+    buffer::GrowableBuffer,
+    font::{fixed_size, FontId, FontMetrics, FontRegistry, TextStyle},
+    layout::{Bbox, TextMeasure},
+    texture::Texture,
+    wrap::wrap_lines,
+};
 use etagere::*;
-use freetype::{face::LoadFlag, Face};
-use image::{DynamicImage, Rgba, RgbaImage};
+use freetype::face::LoadFlag;
+use image::{imageops, DynamicImage, Rgba, RgbaImage};
 use lru::LruCache;
+use std::ops::Range;
 use wgpu::{
-    util::DeviceExt, BindGroup, Buffer, BufferDescriptor, Device, Queue, RenderPass,
-    RenderPipeline, TextureFormat,
+    util::DeviceExt, BindGroup, BindGroupLayout, Buffer, Device, Queue, RenderPass, RenderPipeline,
+    TextureFormat,
 };
 use winit::dpi::PhysicalSize;
 
-pub struct Atlas {
-    size: f32,
-    atlas_image: DynamicImage,
-    allocations: LruCache<char, AtlasChar>,
-    allocator: AtlasAllocator,
+/// A glyph drawn with a font at a size, which is what the atlas caches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct GlyphKey {
+    font: FontId,
+    /// In 26.6 fixed point pixels.
+    size: u32,
+    glyph: u32,
 }
 
+/// Where a rendered glyph is in the atlas. Glyphs without any pixels, like
+/// spaces, are cached too but have no allocation.
 #[derive(Debug, Clone)]
-struct AtlasChar {
-    advance: (f32, f32),
+struct AtlasGlyph {
     pos: (f32, f32),
     size: (f32, f32),
     alloc: Option<Allocation>,
+    /// The `Atlas::frame` it was last drawn in.
+    last_used: u64,
+}
+
+/// The rendered glyphs, packed into one texture. When a new glyph doesn't
+/// fit, glyphs that haven't been used this frame are evicted, and if that's
+/// not enough the atlas doubles in size.
+pub struct Atlas {
+    size: u32,
+    max_size: u32,
+    image: RgbaImage,
+    allocations: LruCache<GlyphKey, AtlasGlyph>,
+    allocator: BucketedAtlasAllocator,
+    /// Counts calls to `clear`. Glyphs used since the last one may already be
+    /// batched, so they have to stay where they are.
+    frame: u64,
+    /// Set when the atlas grew and the texture has to be made again.
+    grown: bool,
+}
+
+impl Atlas {
+    fn new(size: u32, max_size: u32) -> Self {
+        Self {
+            size,
+            max_size,
+            image: RgbaImage::from_pixel(size, size, Rgba([0, 0, 0, 0])),
+            allocations: LruCache::unbounded(),
+            allocator: BucketedAtlasAllocator::new(size2(size as i32, size as i32)),
+            frame: 0,
+            grown: false,
+        }
+    }
+
+    /// Look up a glyph, marking it as used this frame.
+    fn get(&mut self, key: &GlyphKey) -> Option<&AtlasGlyph> {
+        let frame = self.frame;
+        let glyph = self.allocations.get_mut(key)?;
+        glyph.last_used = frame;
+        Some(glyph)
+    }
+
+    fn insert(
+        &mut self,
+        key: GlyphKey,
+        pos: (f32, f32),
+        size: (f32, f32),
+        alloc: Option<Allocation>,
+    ) {
+        let glyph = AtlasGlyph {
+            pos,
+            size,
+            alloc,
+            last_used: self.frame,
+        };
+        self.allocations.put(key, glyph);
+    }
+
+    /// Find room for a `width` by `height` rectangle. `None` if it doesn't fit
+    /// even in an atlas of the biggest size.
+    fn allocate(&mut self, width: u32, height: u32) -> Option<Allocation> {
+        let size = size2(width as i32, height as i32);
+        loop {
+            if let Some(alloc) = self.allocator.allocate(size) {
+                return Some(alloc);
+            }
+
+            // Using a glyph moves it to the front, so if the least recently
+            // used one was used this frame, all of them were.
+            let evictable = matches!(
+                self.allocations.peek_lru(),
+                Some((_, lru)) if lru.last_used < self.frame
+            );
+            if evictable {
+                if let Some(alloc) = self.allocations.pop_lru().and_then(|(_, lru)| lru.alloc) {
+                    self.allocator.deallocate(alloc.id);
+                }
+            } else if self.size * 2 <= self.max_size {
+                self.grow();
+            } else {
+                return None;
+            }
+        }
+    }
+
+    /// Double the size, keeping every glyph where it is.
+    fn grow(&mut self) {
+        self.size *= 2;
+        self.allocator
+            .grow(size2(self.size as i32, self.size as i32));
+        let mut image = RgbaImage::from_pixel(self.size, self.size, Rgba([0, 0, 0, 0]));
+        imageops::replace(&mut image, &self.image, 0, 0);
+        self.image = image;
+        self.grown = true;
+    }
 }
 
 #[repr(C)]
@@ -75,54 +179,44 @@ impl Uniforms {
 pub struct TextRenderer {
     render_pipeline: RenderPipeline,
     vertices: Vec<Vertex>,
-    vertex_buffer: Buffer,
+    vertex_buffer: GrowableBuffer,
 
-    indices: Vec<u16>,
-    index_buffer: Buffer,
+    indices: Vec<u32>,
+    index_buffer: GrowableBuffer,
 
     uniforms_buffer: Buffer,
     uniforms_bind_group: BindGroup,
 
+    texture_bind_group_layout: BindGroupLayout,
     texture_bind_group: BindGroup,
 
     atlas: Atlas,
     atlas_texture: Texture,
 
-    // Hold onto this in case we want to load any new font faces
-    _freetype: freetype::Library,
-    font_size: isize,
-    face: Face,
+    fonts: FontRegistry,
 }
 
 impl TextRenderer {
-    fn cache_char(&mut self, c: char, queue: &Queue) {
-        if self.atlas.allocations.get(&c).is_some() {
+    fn cache_glyph(&mut self, key: GlyphKey, queue: &Queue) {
+        if self.atlas.get(&key).is_some() {
             return;
         }
 
-        self.face.load_char(c as usize, LoadFlag::RENDER).unwrap();
-        let glyph = self.face.glyph();
-
-        let width = glyph.bitmap().width() as u32;
-        let height = glyph.bitmap().rows() as u32;
-
-        if c.is_whitespace() {
-            let atlas_char = AtlasChar {
-                advance: (
-                    glyph.advance().x as f32 / 64.0,
-                    glyph.advance().y as f32 / 64.0,
-                ),
-                size: (width as f32, height as f32),
-                pos: (0.0, 0.0),
-                alloc: None,
-            };
-            self.atlas.allocations.put(c, atlas_char);
-            return;
-        }
+        // Glyphs that can't be drawn at this size are left blank.
+        let glyph = self
+            .fonts
+            .face(key.font, key.size)
+            .filter(|face| face.load_glyph(key.glyph, LoadFlag::RENDER).is_ok())
+            .map(|face| face.glyph());
 
-        if width == 0 || height == 0 {
+        let (width, height) = glyph.map_or((0, 0), |glyph| {
+            (glyph.bitmap().width() as u32, glyph.bitmap().rows() as u32)
+        });
+
+        let Some(glyph) = glyph.filter(|_| width > 0 && height > 0) else {
+            self.atlas.insert(key, (0.0, 0.0), (0.0, 0.0), None);
             return;
-        }
+        };
 
         // Pad allocation 1 pixel on each side to avoid bleeding
         let mut img = RgbaImage::from_pixel(width + 2, height + 2, Rgba([0, 0, 0, 0]));
@@ -142,73 +236,76 @@ impl TextRenderer {
             }
         }
 
-        // Evict characters until we can place the new one
-        loop {
-            if let Some(alloc) = self
-                .atlas
-                .allocator
-                .allocate(size2(img.width() as i32, img.height() as i32))
-            {
-                let atlas_char = AtlasChar {
-                    advance: (
-                        glyph.advance().x as f32 / 64.0,
-                        glyph.advance().y as f32 / 64.0,
-                    ),
-                    size: (width as f32, height as f32),
-                    pos: (
-                        glyph.bitmap_left() as f32,
-                        glyph.bitmap_top() as f32 - height as f32,
-                    ),
-                    alloc: Some(alloc),
-                };
-                let xmin = alloc.rectangle.min.x;
-                let ymin = alloc.rectangle.min.y;
-
-                queue.write_texture(
-                    wgpu::ImageCopyTexture {
-                        aspect: wgpu::TextureAspect::All,
-                        texture: &self.atlas_texture.texture,
-                        mip_level: 0,
-                        origin: wgpu::Origin3d {
-                            x: xmin as u32,
-                            y: ymin as u32,
-                            z: 0,
-                        },
-                    },
-                    &img,
-                    wgpu::ImageDataLayout {
-                        offset: 0,
-                        bytes_per_row: Some(4 * img.width()),
-                        rows_per_image: None,
-                    },
-                    wgpu::Extent3d {
-                        width: img.width(),
-                        height: img.height(),
-                        depth_or_array_layers: 1,
-                    },
-                );
+        let pos = (
+            glyph.bitmap_left() as f32,
+            glyph.bitmap_top() as f32 - height as f32,
+        );
+        let size = (width as f32, height as f32);
 
-                self.atlas.allocations.put(c, atlas_char);
-                return;
-            } else {
-                let lru = self.atlas.allocations.pop_lru().unwrap();
-                if let Some(alloc) = lru.1.alloc {
-                    self.atlas.allocator.deallocate(alloc.id);
-                }
-            }
+        // Too big for even the biggest atlas, so it's never drawn.
+        let Some(alloc) = self.atlas.allocate(img.width(), img.height()) else {
+            self.atlas.insert(key, (0.0, 0.0), (0.0, 0.0), None);
+            return;
+        };
+
+        let xmin = alloc.rectangle.min.x as u32;
+        let ymin = alloc.rectangle.min.y as u32;
+        imageops::replace(&mut self.atlas.image, &img, xmin.into(), ymin.into());
+
+        // A grown atlas gets a new texture with everything in it on `update`.
+        if !self.atlas.grown {
+            queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    aspect: wgpu::TextureAspect::All,
+                    texture: &self.atlas_texture.texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        x: xmin,
+                        y: ymin,
+                        z: 0,
+                    },
+                },
+                &img,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(4 * img.width()),
+                    rows_per_image: None,
+                },
+                wgpu::Extent3d {
+                    width: img.width(),
+                    height: img.height(),
+                    depth_or_array_layers: 1,
+                },
+            );
         }
+
+        self.atlas.insert(key, pos, size, Some(alloc));
     }
 
-    fn generate_img_atlas(size: f32) -> Atlas {
-        let allocator = AtlasAllocator::new(size2(size as i32, size as i32));
-        let img = RgbaImage::from_pixel(size as u32, size as u32, Rgba([0, 0, 0, 0]));
+    fn create_texture_bind_group(
+        device: &Device,
+        layout: &BindGroupLayout,
+        texture: &Texture,
+    ) -> BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&texture.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&texture.sampler),
+                },
+            ],
+            label: Some("texture_bind_group"),
+        })
+    }
 
-        Atlas {
-            atlas_image: DynamicImage::ImageRgba8(img),
-            allocations: LruCache::unbounded(),
-            size,
-            allocator,
-        }
+    fn create_atlas_texture(device: &Device, queue: &Queue, atlas: &Atlas) -> Texture {
+        let image = DynamicImage::ImageRgba8(atlas.image.clone());
+        Texture::from_image(device, queue, &image, Some("Atlas image"))
     }
 
     pub fn new(
@@ -216,15 +313,11 @@ impl TextRenderer {
         queue: &Queue,
         format: &TextureFormat,
         size: PhysicalSize<u32>,
+        fonts: FontRegistry,
     ) -> Self {
-        let font_size = 18;
-        let lib = freetype::Library::init().unwrap();
-        let face = lib.new_face("res/iosevka-extended.ttf", 0).unwrap();
-        face.set_char_size(font_size * 64, 0, 0, 0).unwrap();
-
-        let atlas = Self::generate_img_atlas(256.0);
-        let atlas_texture =
-            Texture::from_image(device, queue, &atlas.atlas_image, Some("Atlas image"));
+        let max_size = device.limits().max_texture_dimension_2d;
+        let atlas = Atlas::new(256.min(max_size), max_size);
+        let atlas_texture = Self::create_atlas_texture(device, queue, &atlas);
 
         let texture_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -248,20 +341,8 @@ impl TextRenderer {
                 ],
                 label: Some("texture_bind_group_layout"),
             });
-        let texture_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &texture_bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&atlas_texture.view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&atlas_texture.sampler),
-                },
-            ],
-            label: Some("texture_bind_group"),
-        });
+        let texture_bind_group =
+            Self::create_texture_bind_group(device, &texture_bind_group_layout, &atlas_texture);
 
         let uniforms = Uniforms::new(size);
 
@@ -349,20 +430,20 @@ impl TextRenderer {
             multiview: None,
         });
 
-        let max_chars = 4096;
-        let vertex_buffer = device.create_buffer(&BufferDescriptor {
-            label: Some("Vertex Buffer"),
-            size: max_chars * 4 * std::mem::size_of::<Vertex>() as u64,
-            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
-
-        let index_buffer = device.create_buffer(&BufferDescriptor {
-            label: Some("Index Buffer"),
-            size: max_chars * 6 * std::mem::size_of::<u16>() as u64,
-            usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
+        let initial_chars = 4096;
+        let vertex_buffer = GrowableBuffer::new(
+            device,
+            "Vertex Buffer",
+            wgpu::BufferUsages::VERTEX,
+            initial_chars * 4 * std::mem::size_of::<Vertex>() as u64,
+        );
+
+        let index_buffer = GrowableBuffer::new(
+            device,
+            "Index Buffer",
+            wgpu::BufferUsages::INDEX,
+            initial_chars * 6 * std::mem::size_of::<u32>() as u64,
+        );
 
         TextRenderer {
             render_pipeline,
@@ -375,27 +456,48 @@ impl TextRenderer {
             uniforms_buffer,
             uniforms_bind_group,
 
+            texture_bind_group_layout,
             texture_bind_group,
 
             atlas,
             atlas_texture,
 
-            _freetype: lib,
-            font_size,
-            face,
+            fonts,
         }
     }
 
     pub fn clear(&mut self) {
         self.indices.clear();
         self.vertices.clear();
+        self.atlas.frame += 1;
     }
 
-    pub fn update(&mut self, size: PhysicalSize<u32>, queue: &Queue) {
+    pub fn update(&mut self, size: PhysicalSize<u32>, device: &Device, queue: &Queue) {
         let uniforms = Uniforms::new(size);
         queue.write_buffer(&self.uniforms_buffer, 0, bytemuck::cast_slice(&[uniforms]));
-        queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(&self.vertices));
-        queue.write_buffer(&self.index_buffer, 0, bytemuck::cast_slice(&self.indices));
+        self.vertex_buffer
+            .write(device, queue, bytemuck::cast_slice(&self.vertices));
+        self.index_buffer
+            .write(device, queue, bytemuck::cast_slice(&self.indices));
+
+        if self.atlas.grown {
+            self.atlas_texture = Self::create_atlas_texture(device, queue, &self.atlas);
+            self.texture_bind_group = Self::create_texture_bind_group(
+                device,
+                &self.texture_bind_group_layout,
+                &self.atlas_texture,
+            );
+            self.atlas.grown = false;
+        }
+    }
+
+    /// Fonts that text can be drawn with.
+    pub fn fonts(&self) -> &FontRegistry {
+        &self.fonts
+    }
+
+    pub fn fonts_mut(&mut self) -> &mut FontRegistry {
+        &mut self.fonts
     }
 
     /// Add a string of text for rendering.
@@ -403,99 +505,95 @@ impl TextRenderer {
     pub fn add_string_to_batch_centered(
         &mut self,
         s: &str,
+        style: &TextStyle,
         queue: &Queue,
         x: f32,
         y: f32,
         text_color: [f32; 4],
     ) {
-        // calculate bottom, fudge it a bit because off center things look more centered
-        let mut y = (y - ((self.font_size as f32 * 0.8) / 2.0)).floor();
+        // A line box centered on y, with the baseline placed inside it.
+        let y = self.baseline(style, y + self.line_height(style) / 2.0);
 
         // calculate left
-        let mut text_len = 0.0;
-        for c in s.chars() {
-            self.cache_char(c, queue);
-            if let Some(glyph) = self.atlas.allocations.get(&c) {
-                text_len += glyph.advance.0;
-            }
-        }
-        let mut x = (x - (text_len / 2.0)).floor();
+        let text_len = self.string_width(s, style);
+        let x = (x - (text_len / 2.0)).floor();
 
         // text is placed using x,y, the bottom left corner of the start of the text.
-        for c in s.chars() {
-            self.cache_char(c, queue);
-            self.add_char_to_batch(c, &mut x, &mut y, text_color);
-        }
+        self.add_line_to_batch(s, style, queue, x, y, text_color);
+    }
+
+    /// The horizontal space a string takes up when drawn on a single line.
+    /// Only shapes the text, so nothing is added to the atlas.
+    pub fn string_width(&mut self, s: &str, style: &TextStyle) -> f32 {
+        self.fonts.string_width(s, style)
+    }
+
+    /// Vertical metrics of the font `style` picks.
+    pub fn metrics(&self, style: &TextStyle) -> FontMetrics {
+        let font = self.fonts.find(&style.family, style.weight);
+        self.fonts.metrics(font, style.size)
     }
 
-    pub fn line_height(&self) -> f32 {
-        self.font_size as f32
+    /// The distance between the baselines of two lines, which is also the
+    /// height of a single line.
+    pub fn line_height(&self, style: &TextStyle) -> f32 {
+        self.fonts.line_height(style)
+    }
+
+    /// Where the baseline goes for a line whose top is at `top`, on a whole
+    /// pixel.
+    fn baseline(&self, style: &TextStyle, top: f32) -> f32 {
+        (top - self.metrics(style).baseline(self.line_height(style))).round()
     }
 
     /// Add a string of text for rendering.
     /// (x, y) is the top left corner of where the text will be placed.
-    /// If wrap_bbox exists, will wrap the line to stay inside the bbox. Must not fail.
+    /// If wrap_bbox exists, the text is wrapped to its width, starting each
+    /// line at its left edge, and lines below its bottom are left out.
     ///
     /// Returns the vertical space it used up.
     /// When wrapping, this could be multiple lines * line_height.
+    #[allow(clippy::too_many_arguments)]
     pub fn add_string_to_batch(
         &mut self,
         s: &str,
+        style: &TextStyle,
         queue: &Queue,
         x: f32,
         y: f32,
         text_color: [f32; 4],
         wrap_bbox: Option<&Bbox>,
     ) -> f32 {
-        // calculate bottom, fudge it a bit because off center things look more centered
-        let mut y = y.floor() - self.line_height();
-        let y_start = y;
-
-        // calculate left
-        let mut x = x.floor();
-
-        // calculate length for wrapping
-        if let Some(wrap_bbox) = wrap_bbox {
-            let mut len = 0.0;
-            for c in s.chars() {
-                self.cache_char(c, queue);
-                let atlas_char = self.atlas.allocations.get(&c).unwrap();
-                len += atlas_char.advance.0;
-            }
-
-            let num_lines = (len / wrap_bbox.width()).ceil() as usize;
-
-            for _ in 0..num_lines {
-                // text is placed using x,y, the bottom left corner of the start of the text.
-                x = wrap_bbox.min.0;
-                for c in s.chars() {
-                    if x > wrap_bbox.max.0 {
-                        x = wrap_bbox.min.0;
-                        y -= self.line_height();
-                    } else if !wrap_bbox.inside((x, y)) {
-                        // Exit early if we've run out of space. No point continuing.
-                        return y_start - y;
-                    }
-                    self.cache_char(c, queue);
-                    self.add_char_to_batch(c, &mut x, &mut y, text_color);
+        let y_start = y.floor();
+        let mut y = y_start;
+        let line_height = self.line_height(style);
+
+        let lines = wrap_lines(self, s, style, wrap_bbox.map(Bbox::width));
+        for line in lines {
+            if let Some(wrap_bbox) = wrap_bbox {
+                // Exit early if we've run out of space. No point continuing.
+                if y - line_height < wrap_bbox.min.1 {
+                    break;
                 }
-                y -= self.line_height();
             }
-            y_start - y
-        } else {
-            // text is placed using x,y, the bottom left corner of the start of the text.
-            for c in s.chars() {
-                self.cache_char(c, queue);
-                self.add_char_to_batch(c, &mut x, &mut y, text_color);
-            }
-            y_start - y
+            let baseline = self.baseline(style, y);
+
+            let x = match wrap_bbox {
+                Some(wrap_bbox) => wrap_bbox.min.0.floor(),
+                None => x.floor(),
+            };
+            self.add_line_to_batch(&s[line.range], style, queue, x, baseline, text_color);
+            y -= line_height;
         }
+        y_start - y
     }
 
     /// If wrap_bbox exists, will wrap the line to stay inside the bbox. Must not fail.
+    #[allow(clippy::too_many_arguments)]
     pub fn add_multiline_string_to_batch(
         &mut self,
         text: &Vec<String>,
+        style: &TextStyle,
         queue: &Queue,
         x: f32,
         y: f32,
@@ -504,28 +602,50 @@ impl TextRenderer {
     ) {
         let mut y = y;
         for line in text {
-            let line = self.add_string_to_batch(line, queue, x, y, text_color, wrap_bbox);
+            let line = self.add_string_to_batch(line, style, queue, x, y, text_color, wrap_bbox);
             y -= line;
         }
     }
 
-    /// Internal details, you should use add_string_to_batch
-    fn add_char_to_batch(
+    /// Shape a single line of text and add its glyphs, starting with the pen
+    /// at (x, y) on the baseline.
+    fn add_line_to_batch(
         &mut self,
-        c: char,
-        x_start: &mut f32,
-        y_start: &mut f32,
+        s: &str,
+        style: &TextStyle,
+        queue: &Queue,
+        x: f32,
+        y: f32,
         text_color: [f32; 4],
     ) {
-        if let Some(glyph) = self.atlas.allocations.get(&c) {
-            let x = *x_start + glyph.pos.0;
-            let y = *y_start + glyph.pos.1;
+        let font = self.fonts.find(&style.family, style.weight);
+        let size = fixed_size(style.size);
+        let mut pen = (x, y);
+        for glyph in self.fonts.shape(font, style.size, s) {
+            let key = GlyphKey {
+                font: glyph.font,
+                size,
+                glyph: glyph.id,
+            };
+            self.cache_glyph(key, queue);
+            // Glyph bitmaps are rendered on whole pixels, so they're placed on
+            // whole pixels too to stay sharp.
+            let x = (pen.0 + glyph.offset.0).round();
+            let y = (pen.1 + glyph.offset.1).round();
+            self.add_glyph_to_batch(key, x, y, text_color);
+            pen.0 += glyph.advance.0;
+            pen.1 += glyph.advance.1;
+        }
+    }
+
+    /// Internal details, you should use add_string_to_batch
+    fn add_glyph_to_batch(&mut self, key: GlyphKey, x: f32, y: f32, text_color: [f32; 4]) {
+        if let Some(glyph) = self.atlas.get(&key) {
+            let x = x + glyph.pos.0;
+            let y = y + glyph.pos.1;
             let w = glyph.size.0;
             let h = glyph.size.1;
 
-            *x_start += glyph.advance.0;
-            *y_start += glyph.advance.1;
-
             if let Some(alloc_rect) = glyph.alloc {
                 // Undo padding
                 let glyph_pos_in_atlas = (
@@ -533,12 +653,14 @@ impl TextRenderer {
                     alloc_rect.rectangle.min.y as f32 + 1.0,
                 );
 
-                let x0 = glyph_pos_in_atlas.0 / self.atlas.size;
-                let x1 = (glyph_pos_in_atlas.0 + glyph.size.0) / self.atlas.size;
-                let y1 = (glyph_pos_in_atlas.1 + glyph.size.1) / self.atlas.size;
-                let y0 = glyph_pos_in_atlas.1 / self.atlas.size;
+                // In pixels, since the atlas can grow after this glyph is
+                // batched. The shader divides by the texture size.
+                let x0 = glyph_pos_in_atlas.0;
+                let x1 = glyph_pos_in_atlas.0 + glyph.size.0;
+                let y1 = glyph_pos_in_atlas.1 + glyph.size.1;
+                let y0 = glyph_pos_in_atlas.1;
 
-                let start = (4 * (self.indices.len() / 6)) as u16;
+                let start = (4 * (self.indices.len() / 6)) as u32;
                 self.indices.push(start);
                 self.indices.push(start + 1);
                 self.indices.push(start + 2);
@@ -570,12 +692,104 @@ impl TextRenderer {
         }
     }
 
-    pub fn render<'rpass>(&'rpass self, rpass: &mut RenderPass<'rpass>) {
+    /// How many indices have been added since the last `clear`. Every glyph
+    /// uses six.
+    pub fn index_count(&self) -> u32 {
+        self.indices.len() as u32
+    }
+
+    /// Draw a range of the glyphs added so far, given as indices.
+    pub fn render<'rpass>(&'rpass self, rpass: &mut RenderPass<'rpass>, indices: Range<u32>) {
         rpass.set_pipeline(&self.render_pipeline);
         rpass.set_bind_group(0, &self.uniforms_bind_group, &[]);
         rpass.set_bind_group(1, &self.texture_bind_group, &[]);
-        rpass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-        rpass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-        rpass.draw_indexed(0..self.indices.len() as u32, 0, 0..1_u32);
+        rpass.set_vertex_buffer(0, self.vertex_buffer.slice());
+        rpass.set_index_buffer(self.index_buffer.slice(), wgpu::IndexFormat::Uint32);
+        rpass.draw_indexed(indices, 0, 0..1_u32);
+    }
+}
+
+impl TextMeasure for TextRenderer {
+    fn string_width(&mut self, s: &str, style: &TextStyle) -> f32 {
+        TextRenderer::string_width(self, s, style)
+    }
+
+    fn line_height(&self, style: &TextStyle) -> f32 {
+        TextRenderer::line_height(self, style)
+    }
+
+    fn caret_positions(&mut self, s: &str, style: &TextStyle) -> Vec<(usize, f32)> {
+        self.fonts.caret_positions(s, style)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An atlas for glyphs of one font.
+    struct TestAtlas {
+        atlas: Atlas,
+        font: FontId,
+    }
+
+    impl TestAtlas {
+        fn new(size: u32, max_size: u32) -> Self {
+            Self {
+                atlas: Atlas::new(size, max_size),
+                font: FontRegistry::new().fallbacks()[0],
+            }
+        }
+
+        fn key(&self, glyph: u32) -> GlyphKey {
+            GlyphKey {
+                font: self.font,
+                size: 0,
+                glyph,
+            }
+        }
+
+        /// Put `glyphs`, each `size` pixels square, in the atlas.
+        fn fill(&mut self, glyphs: Range<u32>, size: u32) {
+            for glyph in glyphs {
+                let alloc = self.atlas.allocate(size, size);
+                assert!(alloc.is_some());
+                self.atlas
+                    .insert(self.key(glyph), (0.0, 0.0), (0.0, 0.0), alloc);
+            }
+        }
+
+        fn contains(&self, glyph: u32) -> bool {
+            self.atlas.allocations.contains(&self.key(glyph))
+        }
+    }
+
+    #[test]
+    fn atlas_grows_instead_of_evicting_glyphs_used_this_frame() {
+        let mut test = TestAtlas::new(64, 1024);
+        test.fill(0..100, 20);
+
+        assert!(test.atlas.size > 64);
+        assert!(test.atlas.grown);
+        assert!((0..100).all(|glyph| test.contains(glyph)));
+    }
+
+    #[test]
+    fn atlas_evicts_glyphs_not_used_this_frame() {
+        let mut test = TestAtlas::new(64, 128);
+        test.fill(0..4, 20);
+        test.atlas.frame += 1;
+        test.atlas.get(&test.key(0));
+        test.fill(4..8, 20);
+
+        assert!(test.contains(0));
+        assert!(!test.contains(1));
+    }
+
+    #[test]
+    fn atlas_gives_up_when_it_cant_grow() {
+        let mut test = TestAtlas::new(64, 128);
+        assert!(test.atlas.allocate(200, 200).is_none());
+        assert_eq!(test.atlas.size, 128);
     }
 }
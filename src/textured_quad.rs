@@ -1,7 +1,7 @@
-use crate::{layout::Bbox, texture::Texture};
+use crate::{buffer::GrowableBuffer, layout::Bbox, texture::Texture};
+use std::ops::Range;
 use wgpu::{
-    util::DeviceExt, BindGroup, Buffer, BufferDescriptor, Device, Queue, RenderPass,
-    RenderPipeline, TextureFormat,
+    util::DeviceExt, BindGroup, Buffer, Device, Queue, RenderPass, RenderPipeline, TextureFormat,
 };
 use winit::dpi::PhysicalSize;
 
@@ -116,7 +116,7 @@ pub struct TexturedQuadRenderer {
     num_indices: u32,
 
     instances: Vec<Quad>,
-    instance_buffer: Buffer,
+    instance_buffer: GrowableBuffer,
 
     uniforms: Uniforms,
     uniforms_buffer: Buffer,
@@ -273,14 +273,14 @@ impl TexturedQuadRenderer {
         });
         let num_indices = INDICES.len() as u32;
 
-        let max_instances = 1024;
+        let initial_instances = 1024;
         let instances = vec![];
-        let instance_buffer = device.create_buffer(&BufferDescriptor {
-            label: Some("Instance Buffer"),
-            size: max_instances * std::mem::size_of::<Quad>() as u64,
-            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
+        let instance_buffer = GrowableBuffer::new(
+            device,
+            "Instance Buffer",
+            wgpu::BufferUsages::VERTEX,
+            initial_instances * std::mem::size_of::<Quad>() as u64,
+        );
 
         TexturedQuadRenderer {
             render_pipeline,
@@ -318,27 +318,30 @@ impl TexturedQuadRenderer {
         self.instances.clear();
     }
 
-    pub fn update(&mut self, size: PhysicalSize<u32>, queue: &Queue) {
+    pub fn update(&mut self, size: PhysicalSize<u32>, device: &Device, queue: &Queue) {
         self.uniforms = Uniforms::new(size);
         queue.write_buffer(
             &self.uniforms_buffer,
             0,
             bytemuck::cast_slice(&[self.uniforms]),
         );
-        queue.write_buffer(
-            &self.instance_buffer,
-            0,
-            bytemuck::cast_slice(&self.instances),
-        );
+        self.instance_buffer
+            .write(device, queue, bytemuck::cast_slice(&self.instances));
+    }
+
+    /// How many quads have been added since the last `clear`.
+    pub fn instance_count(&self) -> u32 {
+        self.instances.len() as u32
     }
 
-    pub fn render<'rpass>(&'rpass self, rpass: &mut RenderPass<'rpass>) {
+    /// Draw a range of the quads added so far, in the order they were added.
+    pub fn render<'rpass>(&'rpass self, rpass: &mut RenderPass<'rpass>, instances: Range<u32>) {
         rpass.set_pipeline(&self.render_pipeline);
         rpass.set_bind_group(0, &self.uniforms_bind_group, &[]);
         rpass.set_bind_group(1, &self.texture_bind_group, &[]);
         rpass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-        rpass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+        rpass.set_vertex_buffer(1, self.instance_buffer.slice());
         rpass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-        rpass.draw_indexed(0..self.num_indices, 0, 0..self.instances.len() as u32);
+        rpass.draw_indexed(0..self.num_indices, 0, instances);
     }
 }
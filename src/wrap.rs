@@ -0,0 +1,148 @@
+use crate::{font::TextStyle, layout::TextMeasure};
+use std::ops::Range;
+use unicode_linebreak::{linebreaks, BreakOpportunity};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// One line of wrapped text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Line {
+    /// Where the line is in the wrapped string. Whitespace and newlines at the
+    /// end of the line are left out.
+    pub range: Range<usize>,
+    pub width: f32,
+}
+
+/// Break `text`, drawn in `style`, into lines that fit in `max_width`, or only at newlines if
+/// there's no `max_width`.
+///
+/// Lines break at Unicode line break opportunities, such as after spaces and
+/// hyphens. A word that doesn't fit on a line of its own is broken between
+/// grapheme clusters instead, so marks stay with the letters they're on.
+/// Whitespace at the end of a line doesn't count towards its width, so it
+/// never causes a break.
+pub fn wrap_lines<M: TextMeasure + ?Sized>(
+    measure: &mut M,
+    text: &str,
+    style: &TextStyle,
+    max_width: Option<f32>,
+) -> Vec<Line> {
+    let max_width = max_width.unwrap_or(f32::INFINITY);
+    let mut lines = vec![];
+
+    let mut start = 0;
+    // Width of the current line with and without its trailing whitespace.
+    let mut width = 0.0;
+    let mut visible_width = 0.0;
+
+    let mut segment_start = 0;
+    for (segment_end, opportunity) in linebreaks(text) {
+        let segment = &text[segment_start..segment_end];
+        let word = segment.trim_end();
+        let word_width = measure.string_width(word, style);
+
+        if start < segment_start && width + word_width > max_width {
+            end_line(&mut lines, text, &mut start, segment_start, visible_width);
+            width = 0.0;
+        }
+
+        if word_width > max_width {
+            for (i, grapheme) in word.grapheme_indices(true) {
+                let grapheme_width = measure.string_width(grapheme, style);
+                if start < segment_start + i && width + grapheme_width > max_width {
+                    end_line(&mut lines, text, &mut start, segment_start + i, width);
+                    width = 0.0;
+                }
+                width += grapheme_width;
+            }
+            visible_width = width;
+        } else {
+            visible_width = width + word_width;
+        }
+        width = visible_width + measure.string_width(&segment[word.len()..], style);
+
+        if opportunity == BreakOpportunity::Mandatory {
+            end_line(&mut lines, text, &mut start, segment_end, visible_width);
+            width = 0.0;
+            visible_width = 0.0;
+        }
+        segment_start = segment_end;
+    }
+
+    if lines.is_empty() {
+        lines.push(Line {
+            range: 0..0,
+            width: 0.0,
+        });
+    }
+    lines
+}
+
+/// Finish the line that starts at `start` and ends at `end`, and start the next
+/// one there.
+fn end_line(lines: &mut Vec<Line>, text: &str, start: &mut usize, end: usize, width: f32) {
+    let line = text[*start..end].trim_end();
+    lines.push(Line {
+        range: *start..*start + line.len(),
+        width,
+    });
+    *start = end;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::tests::FixedMeasure;
+
+    fn wrap(text: &str, max_width: Option<f32>) -> Vec<(&str, f32)> {
+        wrap_lines(&mut FixedMeasure, text, &TextStyle::default(), max_width)
+            .into_iter()
+            .map(|line| (&text[line.range], line.width))
+            .collect()
+    }
+
+    #[test]
+    fn breaks_between_words() {
+        assert_eq!(
+            wrap("one two three", Some(70.0)),
+            vec![("one two", 70.0), ("three", 50.0)]
+        );
+    }
+
+    #[test]
+    fn trailing_whitespace_doesnt_count() {
+        assert_eq!(
+            wrap("abc   def", Some(30.0)),
+            vec![("abc", 30.0), ("def", 30.0)]
+        );
+    }
+
+    #[test]
+    fn long_words_break_between_chars() {
+        assert_eq!(
+            wrap("a abcdefgh", Some(30.0)),
+            vec![("a", 10.0), ("abc", 30.0), ("def", 30.0), ("gh", 20.0)]
+        );
+    }
+
+    #[test]
+    fn long_words_keep_marks_with_their_letters() {
+        // The accent is a char of its own, so the "é" is 20 pixels wide.
+        assert_eq!(
+            wrap("ae\u{301}bc", Some(20.0)),
+            vec![("a", 10.0), ("e\u{301}", 20.0), ("bc", 20.0)]
+        );
+    }
+
+    #[test]
+    fn newlines_always_break() {
+        assert_eq!(
+            wrap("ab\n\ncd", None),
+            vec![("ab", 20.0), ("", 0.0), ("cd", 20.0)]
+        );
+    }
+
+    #[test]
+    fn empty_text_is_one_empty_line() {
+        assert_eq!(wrap("", Some(10.0)), vec![("", 0.0)]);
+    }
+}